@@ -0,0 +1,244 @@
+use crate::{
+    gpu::{Aggregation, GpuSelector, MemberHealth, ResolvedAggregation},
+    hwmon,
+    sensor::{HwmonSource, TemperatureSource},
+};
+use clap::ValueEnum;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum AmdSensor {
+    Edge,
+    Junction,
+    Memory,
+}
+
+impl AmdSensor {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Edge => "edge",
+            Self::Junction => "junction",
+            Self::Memory => "mem",
+        }
+    }
+}
+
+pub struct AmdgpuChip {
+    pub dir: PathBuf,
+    pub pci_bus_id: Option<String>,
+}
+
+impl AmdgpuChip {
+    pub fn sensor_path(&self, sensor: AmdSensor) -> Option<PathBuf> {
+        let labelled = (1..=8).find_map(|i| {
+            let label = fs::read_to_string(self.dir.join(format!("temp{}_label", i))).ok()?;
+            let input = self.dir.join(format!("temp{}_input", i));
+            (label.trim() == sensor.label() && input.exists()).then_some(input)
+        });
+        if labelled.is_some() {
+            return labelled;
+        }
+
+        // 旧内核只有不带标签的 temp1_input，即 edge 温度
+        let edge = self.dir.join("temp1_input");
+        (sensor == AmdSensor::Edge && edge.exists()).then_some(edge)
+    }
+
+    pub fn temperatures(&self) -> Vec<(AmdSensor, Option<u32>)> {
+        [AmdSensor::Edge, AmdSensor::Junction, AmdSensor::Memory]
+            .into_iter()
+            .filter_map(|sensor| {
+                let path = self.sensor_path(sensor)?;
+                let temp = HwmonSource::new(path.to_string_lossy().into_owned())
+                    .ok()
+                    .and_then(|mut s| s.read_temp());
+                Some((sensor, temp))
+            })
+            .collect()
+    }
+
+    fn matches_pci(&self, bus_id: &str) -> bool {
+        let Some(bus_id) = normalize_pci(bus_id) else {
+            return false;
+        };
+        self.pci_bus_id.as_deref().and_then(normalize_pci) == Some(bus_id)
+    }
+}
+
+// 统一为 domain:bus:dev.fn 形式：sysfs 的域为 4 位 (0000:03:00.0)，NVML 为 8 位 (00000000:03:00.0)，
+// 省略域时视为 0
+fn normalize_pci(id: &str) -> Option<String> {
+    let mut parts: Vec<&str> = id.trim().split(':').collect();
+    if parts.len() == 2 {
+        parts.insert(0, "0");
+    }
+    let [domain, bus, slot] = parts[..] else {
+        return None;
+    };
+    let (device, function) = slot.split_once('.')?;
+    let domain = u32::from_str_radix(domain, 16).ok()?;
+    let bus = u8::from_str_radix(bus, 16).ok()?;
+    let device = u8::from_str_radix(device, 16).ok().filter(|&d| d < 0x20)?;
+    let function = u8::from_str_radix(function, 16).ok().filter(|&f| f < 8)?;
+    Some(format!("{:04x}:{:02x}:{:02x}.{:x}", domain, bus, device, function))
+}
+
+pub fn list_chips(root: &Path) -> Vec<AmdgpuChip> {
+    hwmon::list_hwmon_dirs(root)
+        .into_iter()
+        .filter(|dir| hwmon::read_name(dir).as_deref() == Some("amdgpu"))
+        .map(|dir| {
+            let pci_bus_id = fs::canonicalize(dir.join("device"))
+                .ok()
+                .and_then(|p| p.file_name().map(|n| n.to_string_lossy().into_owned()));
+            AmdgpuChip { dir, pci_bus_id }
+        })
+        .collect()
+}
+
+pub struct AmdgpuSource {
+    sensors: Vec<HwmonSource>,
+    aggregation: ResolvedAggregation,
+    health: MemberHealth,
+}
+
+impl AmdgpuSource {
+    pub fn discover(
+        root: &Path,
+        selectors: &[GpuSelector],
+        aggregation: &Aggregation,
+        sensor: AmdSensor,
+    ) -> Result<Self, String> {
+        let chips = list_chips(root);
+        if chips.is_empty() {
+            return Err(format!("{} 下没有 amdgpu 设备", root.display()));
+        }
+
+        let find = |selector: &GpuSelector| -> Result<usize, String> {
+            match selector {
+                GpuSelector::Index(index) => Some(*index as usize).filter(|&i| i < chips.len()),
+                GpuSelector::PciBusId(bus_id) => chips.iter().position(|c| c.matches_pci(bus_id)),
+                GpuSelector::Uuid(_) => return Err(format!("amdgpu 不支持按 UUID 选择 GPU ({})", selector)),
+            }
+            .ok_or_else(|| format!("amdgpu 设备 ({}) 不存在", selector))
+        };
+
+        let mut selected = Vec::with_capacity(selectors.len().max(1));
+        for selector in selectors {
+            let pos = find(selector)?;
+            if !selected.contains(&pos) {
+                selected.push(pos);
+            }
+        }
+        if selected.is_empty() {
+            selected.push(0);
+        }
+
+        let aggregation = match aggregation {
+            Aggregation::Max => ResolvedAggregation::Max,
+            Aggregation::Average => ResolvedAggregation::Average,
+            Aggregation::Gpu(selector) => {
+                let pos = find(selector)?;
+                ResolvedAggregation::Single(
+                    selected
+                        .iter()
+                        .position(|&p| p == pos)
+                        .ok_or_else(|| format!("聚合使用的 GPU ({}) 不在 --gpu 列表中", selector))?,
+                )
+            }
+        };
+
+        let mut sensors = Vec::with_capacity(selected.len());
+        for pos in selected {
+            let chip = &chips[pos];
+            let path = chip.sensor_path(sensor).ok_or_else(|| {
+                format!("amdgpu 设备 {} 没有 {} 温度传感器", chip.dir.display(), sensor.label())
+            })?;
+            sensors.push(HwmonSource::new(path.to_string_lossy().into_owned())?);
+        }
+
+        Ok(Self {
+            sensors,
+            aggregation,
+            health: MemberHealth::default(),
+        })
+    }
+
+    pub fn paths(&self) -> Vec<&str> {
+        self.sensors.iter().map(|s| s.path()).collect()
+    }
+}
+
+impl TemperatureSource for AmdgpuSource {
+    fn read_temp(&mut self) -> Option<u32> {
+        let sensors = &mut self.sensors;
+        let result = self.aggregation.combine(sensors.len(), |pos| sensors[pos].read_temp());
+        self.health.track(result, |pos| self.sensors[pos].path().to_string())
+    }
+
+    fn gpu(&self) -> Option<String> {
+        Some(self.paths().join(","))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::TempDir;
+
+    fn fake_card(root: &Path, hwmon: &str, temps: &[(&str, u32)]) {
+        let dir = root.join(hwmon);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("name"), "amdgpu\n").unwrap();
+        for (i, (label, millis)) in temps.iter().enumerate() {
+            fs::write(dir.join(format!("temp{}_label", i + 1)), format!("{}\n", label)).unwrap();
+            fs::write(dir.join(format!("temp{}_input", i + 1)), format!("{}\n", millis)).unwrap();
+        }
+    }
+
+    #[test]
+    fn reads_labelled_sensors_from_fake_sysfs() {
+        let root = TempDir::new("amdgpu");
+        fs::create_dir_all(root.join("hwmon0")).unwrap();
+        fs::write(root.join("hwmon0/name"), "k10temp\n").unwrap();
+        fake_card(&root, "hwmon1", &[("edge", 45000), ("junction", 61500), ("mem", 70000)]);
+        fake_card(&root, "hwmon2", &[("edge", 50000), ("junction", 58000), ("mem", 66000)]);
+
+        let read = |selectors: &[GpuSelector], aggregation: Aggregation, sensor| {
+            AmdgpuSource::discover(&root, selectors, &aggregation, sensor)
+                .unwrap()
+                .read_temp()
+        };
+        assert_eq!(read(&[], Aggregation::Max, AmdSensor::Edge), Some(45));
+        assert_eq!(read(&[], Aggregation::Max, AmdSensor::Junction), Some(62));
+        assert_eq!(read(&[GpuSelector::Index(1)], Aggregation::Max, AmdSensor::Memory), Some(66));
+
+        let both = [GpuSelector::Index(0), GpuSelector::Index(1)];
+        assert_eq!(read(&both, Aggregation::Max, AmdSensor::Edge), Some(50));
+        assert_eq!(read(&both, Aggregation::Average, AmdSensor::Memory), Some(68));
+        assert_eq!(read(&both, Aggregation::Gpu(GpuSelector::Index(0)), AmdSensor::Junction), Some(62));
+
+        assert!(AmdgpuSource::discover(&root, &[GpuSelector::Index(2)], &Aggregation::Max, AmdSensor::Edge).is_err());
+    }
+
+    #[test]
+    fn matches_pci_bus_ids_exactly() {
+        let chip = |id: &str| AmdgpuChip {
+            dir: PathBuf::new(),
+            pci_bus_id: Some(id.to_string()),
+        };
+        assert!(chip("0000:03:00.0").matches_pci("0000:03:00.0"));
+        assert!(chip("0000:03:00.0").matches_pci("00000000:03:00.0"));
+        assert!(chip("0000:0a:00.0").matches_pci("0A:00.0"));
+        assert!(chip("0000:01:00.0").matches_pci("1:00.0"));
+        assert!(!chip("0000:11:00.0").matches_pci("1:00.0"));
+        assert!(!chip("0001:03:00.0").matches_pci("03:00.0"));
+        assert!(!chip("0000:03:00.0").matches_pci("03:00"));
+        assert!(!chip("0000:03:00.0").matches_pci("0000:03:00.1"));
+        assert_eq!(normalize_pci("00000000:03:00.0").as_deref(), Some("0000:03:00.0"));
+        assert_eq!(normalize_pci("garbage"), None);
+    }
+}
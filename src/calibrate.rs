@@ -0,0 +1,204 @@
+use crate::pwm::PwmOutput;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    sync::atomic::{AtomicBool, Ordering},
+    thread::sleep,
+    time::{Duration, Instant},
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct ProfilePoint {
+    pub pwm: u8,
+    pub rpm: u32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct FanProfile {
+    pub pwm_path: String,
+    pub start_pwm: u8,
+    pub stop_pwm: u8,
+    pub max_rpm: u32,
+    pub points: Vec<ProfilePoint>,
+}
+
+impl FanProfile {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("无法读取风扇配置档 {}: {}", path, e))?;
+        toml::from_str(&text).map_err(|e| format!("风扇配置档 {} 格式错误: {}", path, e))
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let text = toml::to_string_pretty(self)
+            .map_err(|e| format!("无法序列化风扇配置档: {}", e))?;
+        fs::write(path, text).map_err(|e| format!("无法写入风扇配置档 {}: {}", path, e))
+    }
+}
+
+// tach 按当前 PWM 返回转速，测试中可以直接模拟风扇的启停
+pub struct Calibrator<'a, F> {
+    output: &'a mut PwmOutput,
+    tach: F,
+    settle: Duration,
+    running: &'a AtomicBool,
+}
+
+impl<'a, F: FnMut(u8) -> Option<u32>> Calibrator<'a, F> {
+    pub fn new(
+        output: &'a mut PwmOutput,
+        tach: F,
+        settle: Duration,
+        running: &'a AtomicBool,
+    ) -> Self {
+        Self {
+            output,
+            tach,
+            settle,
+            running,
+        }
+    }
+
+    fn wait(&self, duration: Duration) -> Result<(), String> {
+        let deadline = Instant::now() + duration;
+        while Instant::now() < deadline {
+            if !self.running.load(Ordering::Relaxed) {
+                return Err("校准已中断".to_string());
+            }
+            sleep(Duration::from_millis(100).min(deadline - Instant::now()));
+        }
+        Ok(())
+    }
+
+    fn measure(&mut self, pwm: u8, settle: Duration) -> Result<u32, String> {
+        self.output.set_fan_speed(pwm).map_err(|e| e.to_string())?;
+        self.wait(settle)?;
+        let rpm = (self.tach)(pwm).ok_or_else(|| format!("PWM {} 时无法读取风扇转速", pwm))?;
+        println!("PWM {:>3} -> {:>5} RPM", pwm, rpm);
+        Ok(rpm)
+    }
+
+    // 先满速测最大转速，再逐级降速找到停转点，最后从停转状态逐级升速找到启动点
+    pub fn run(&mut self, step: u8) -> Result<FanProfile, String> {
+        let step = step.max(1);
+        let mut points = Vec::new();
+
+        let max_rpm = self.measure(255, self.settle * 2)?;
+        if max_rpm == 0 {
+            return Err("PWM 255 时转速为 0，请确认转速文件与 PWM 通道对应".to_string());
+        }
+        points.push(ProfilePoint { pwm: 255, rpm: max_rpm });
+
+        let mut stop_pwm = 255;
+        let mut stopped_at = None;
+        let mut pwm = 255u8;
+        while pwm > 0 {
+            pwm = pwm.saturating_sub(step);
+            let rpm = self.measure(pwm, self.settle)?;
+            points.push(ProfilePoint { pwm, rpm });
+            if rpm == 0 {
+                stopped_at = Some(pwm);
+                break;
+            }
+            stop_pwm = pwm;
+        }
+
+        let start_pwm = match stopped_at {
+            None => 0,
+            Some(stopped) => {
+                self.measure(0, self.settle)?;
+                let mut pwm = stopped;
+                loop {
+                    pwm = pwm.saturating_add(step);
+                    if self.measure(pwm, self.settle)? > 0 {
+                        break pwm;
+                    }
+                    if pwm == 255 {
+                        return Err("从停转状态升速到 255 仍未检测到转速".to_string());
+                    }
+                }
+            }
+        };
+
+        points.sort_by_key(|p| p.pwm);
+        points.dedup_by_key(|p| p.pwm);
+
+        Ok(FanProfile {
+            pwm_path: self.output.path().to_string(),
+            start_pwm,
+            stop_pwm,
+            max_rpm,
+            points,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        hwmon::SysfsValue,
+        testutil::{fake_pwm, TempDir},
+    };
+
+    fn calibrate(dir: &TempDir, step: u8, tach: impl FnMut(u8) -> Option<u32>) -> Result<FanProfile, String> {
+        let running = AtomicBool::new(true);
+        let mut output = PwmOutput::new(dir.path_str("pwm1"), None).unwrap();
+        Calibrator::new(&mut output, tach, Duration::ZERO, &running).run(step)
+    }
+
+    #[test]
+    fn sweeps_and_round_trips_profile() {
+        let dir = TempDir::new("calibrate");
+        fs::write(dir.join("pwm1"), "0").unwrap();
+        fs::write(dir.join("pwm1_enable"), "2").unwrap();
+        fs::write(dir.join("fan1_input"), "1200\n").unwrap();
+        let pwm_path = dir.path_str("pwm1");
+
+        let mut tach = SysfsValue::new(dir.path_str("fan1_input"));
+        let profile = calibrate(&dir, 85, |_| tach.read()).unwrap();
+
+        // 转速始终不为 0，说明风扇在 PWM 0 时也不会停转
+        assert_eq!(profile.pwm_path, pwm_path);
+        assert_eq!((profile.start_pwm, profile.stop_pwm, profile.max_rpm), (0, 0, 1200));
+        let pwms: Vec<u8> = profile.points.iter().map(|p| p.pwm).collect();
+        assert_eq!(pwms, [0, 85, 170, 255]);
+
+        let saved = dir.join("profile.toml").to_string_lossy().into_owned();
+        profile.save(&saved).unwrap();
+        assert_eq!(FanProfile::load(&saved).unwrap(), profile);
+    }
+
+    #[test]
+    fn finds_stop_and_start_thresholds() {
+        let (dir, _) = fake_pwm("calibrate-stop", "0", "2");
+
+        // 低于 100 停转，停转后需要 130 以上才能重新启动
+        let mut spinning = true;
+        let profile = calibrate(&dir, 20, |pwm| {
+            spinning = if spinning { pwm >= 100 } else { pwm >= 130 };
+            Some(if spinning { u32::from(pwm) * 10 } else { 0 })
+        })
+        .unwrap();
+        assert_eq!((profile.start_pwm, profile.stop_pwm, profile.max_rpm), (135, 115, 2550));
+        let points: Vec<(u8, u32)> = profile.points.iter().map(|p| (p.pwm, p.rpm)).collect();
+        assert_eq!(
+            points,
+            [(95, 0), (115, 1150), (135, 1350), (155, 1550), (175, 1750), (195, 1950), (215, 2150), (235, 2350), (255, 2550)]
+        );
+
+        // 停转后一直无法启动
+        let mut spinning = true;
+        let err = calibrate(&dir, 20, |pwm| {
+            spinning = spinning && pwm >= 100;
+            Some(if spinning { 1000 } else { 0 })
+        })
+        .unwrap_err();
+        assert!(err.contains("升速到 255"), "{}", err);
+
+        let err = calibrate(&dir, 20, |_| Some(0)).unwrap_err();
+        assert!(err.contains("PWM 255 时转速为 0"), "{}", err);
+    }
+}
@@ -0,0 +1,305 @@
+use crate::{
+    amdgpu::AmdSensor,
+    fan::ControlMode,
+    filter::FilterKind,
+    gpu::{Aggregation, GpuSelector},
+    hwmon::{self, PwmTarget},
+    info::InfoFormat,
+    logging::LogFormat,
+    metric::MetricExpr,
+    pid::PidOverrides,
+};
+use clap::{Parser, Subcommand};
+use log::LevelFilter;
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct HwmonRootArgs {
+    /// hwmon 设备所在目录
+    #[arg(long, default_value = hwmon::SYSFS_HWMON)]
+    pub hwmon_root: String,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct PwmTargetArgs {
+    /// PWM 控制文件路径，如 /sys/class/hwmon/hwmon2/pwm1
+    pub pwm_path: Option<String>,
+    /// 按 hwmon 芯片名选择设备，需配合 --pwm-channel
+    #[arg(long, conflicts_with_all = ["pwm_path", "hwmon_device"], requires = "pwm_channel")]
+    pub hwmon_name: Option<String>,
+    /// 按 device 链接（如 PCI 地址）选择设备，需配合 --pwm-channel
+    #[arg(long, conflicts_with = "pwm_path", requires = "pwm_channel")]
+    pub hwmon_device: Option<String>,
+    /// PWM 通道号，对应 pwmN
+    #[arg(long)]
+    pub pwm_channel: Option<u32>,
+    #[command(flatten)]
+    pub root: HwmonRootArgs,
+}
+
+impl PwmTargetArgs {
+    pub fn target(&self) -> Option<PwmTarget> {
+        match (&self.pwm_path, &self.hwmon_name, &self.hwmon_device, self.pwm_channel) {
+            (Some(path), _, _, _) => Some(PwmTarget::Path(path.clone())),
+            (None, Some(name), _, Some(channel)) => Some(PwmTarget::Chip {
+                name: name.clone(),
+                channel,
+            }),
+            (None, None, Some(device), Some(channel)) => Some(PwmTarget::Device {
+                device: device.clone(),
+                channel,
+            }),
+            _ => None,
+        }
+    }
+}
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct RunArgs {
+    #[command(flatten)]
+    pub target: PwmTargetArgs,
+    /// 温度检查间隔（秒）
+    #[arg(long, default_value_t = 2.0)]
+    pub interval: f64,
+    /// TOML 配置文件路径
+    #[arg(long)]
+    pub config: Option<String>,
+    /// 降速前温度需回落的度数
+    #[arg(long)]
+    pub hysteresis: Option<u32>,
+    /// 按索引选择 GPU，逗号分隔
+    #[arg(long, value_delimiter = ',')]
+    pub gpu: Vec<GpuSelector>,
+    /// 按 UUID 选择 GPU，逗号分隔
+    #[arg(long, value_delimiter = ',')]
+    pub gpu_uuid: Vec<String>,
+    /// 按 PCI 总线地址选择 GPU，逗号分隔
+    #[arg(long, value_delimiter = ',')]
+    pub gpu_pci: Vec<String>,
+    /// 多块 GPU 的温度合并方式：max、average 或 gpu:<选择器>
+    #[arg(long, default_value = "max")]
+    pub aggregate: Aggregation,
+    /// 改用 hwmon 温度文件（tempN_input）作为温度来源
+    #[arg(long, conflicts_with_all = ["gpu", "gpu_uuid", "gpu_pci"])]
+    pub temp_input: Option<String>,
+    /// amdgpu 使用的温度传感器
+    #[arg(long, value_enum, default_value = "edge")]
+    pub amd_sensor: AmdSensor,
+    /// 温度指标，如 core、memory 或 max(core, memory)
+    #[arg(long)]
+    pub metric: Option<MetricExpr>,
+    /// 控制方式
+    #[arg(long, value_enum)]
+    pub mode: Option<ControlMode>,
+    /// 温度滤波器：sma:<采样数> 或 ema:<alpha>
+    #[arg(long)]
+    pub filter: Option<FilterKind>,
+    /// 升速速率上限（PWM/秒）
+    #[arg(long)]
+    pub ramp_up: Option<f64>,
+    /// 降速速率上限（PWM/秒）
+    #[arg(long)]
+    pub ramp_down: Option<f64>,
+    /// 风扇转速文件（fanN_input），默认按 PWM 通道推断
+    #[arg(long)]
+    pub fan_input: Option<String>,
+    /// PWM 高于 --stall-pwm 而转速为 0 超过该秒数即视为停转
+    #[arg(long)]
+    pub stall_timeout: Option<f64>,
+    /// 停转检测的 PWM 阈值，不高于该值时不检测
+    #[arg(long)]
+    pub stall_pwm: Option<u8>,
+    /// 检测到停转或恢复时执行的 shell 命令
+    #[arg(long)]
+    pub on_stall: Option<String>,
+    /// 检测到停转时退出，--exit-on-stall=false 可关闭配置文件中的设置
+    #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    pub exit_on_stall: Option<bool>,
+    /// 连续读取失败多少次后进入失效保护
+    #[arg(long)]
+    pub failsafe_errors: Option<u32>,
+    /// 读取失败持续多少秒后进入失效保护
+    #[arg(long)]
+    pub failsafe_timeout: Option<f64>,
+    /// 失效保护时使用的 PWM
+    #[arg(long)]
+    pub failsafe_pwm: Option<u8>,
+    /// 检查 pwm_enable 是否被改回自动模式的间隔（秒），0 表示不检查
+    #[arg(long)]
+    pub mode_check_interval: Option<f64>,
+    /// 写入 PWM 后回读校验，--verify-writes=false 可关闭配置文件中的设置
+    #[arg(long, num_args = 0..=1, require_equals = true, default_missing_value = "true")]
+    pub verify_writes: Option<bool>,
+    /// 回读校验允许的 PWM 偏差
+    #[arg(long)]
+    pub verify_tolerance: Option<u8>,
+    /// calibrate 生成的风扇配置档，用于确定启动 PWM
+    #[arg(long)]
+    pub profile: Option<String>,
+    /// 退出时写入 pwm_enable 的值，默认恢复启动时的模式
+    #[arg(long)]
+    pub exit_mode: Option<u8>,
+    #[command(flatten)]
+    pub pid: PidOverrides,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct InfoArgs {
+    #[command(flatten)]
+    pub root: HwmonRootArgs,
+    /// 输出格式
+    #[arg(long, value_enum, default_value = "text")]
+    pub format: InfoFormat,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct ListPwmArgs {
+    #[command(flatten)]
+    pub root: HwmonRootArgs,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct SetArgs {
+    #[command(flatten)]
+    pub target: PwmTargetArgs,
+    /// 要设置的 PWM 值（0-255）
+    #[arg(long)]
+    pub value: u8,
+    /// 退出时写入 pwm_enable 的值，默认恢复启动时的模式
+    #[arg(long)]
+    pub exit_mode: Option<u8>,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct CalibrateArgs {
+    #[command(flatten)]
+    pub target: PwmTargetArgs,
+    /// 风扇转速文件（fanN_input），默认按 PWM 通道推断
+    #[arg(long)]
+    pub fan_input: Option<String>,
+    /// 校准结果的输出文件
+    #[arg(long, short)]
+    pub output: String,
+    /// 每次降低的 PWM 步长
+    #[arg(long, default_value_t = 5)]
+    pub step: u8,
+    /// 每次调整后等待转速稳定的秒数
+    #[arg(long, default_value_t = 4.0)]
+    pub settle: f64,
+}
+
+#[derive(clap::Args, Debug, Clone)]
+pub struct StatusArgs {
+    #[command(flatten)]
+    pub target: PwmTargetArgs,
+    /// TOML 配置文件路径，显示其中配置的所有风扇
+    #[arg(long)]
+    pub config: Option<String>,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum Command {
+    /// 按温度控制风扇（默认）
+    Run(Box<RunArgs>),
+    /// 显示 GPU 型号、温度、功耗、频率与温度阈值，支持 --format json
+    Info(InfoArgs),
+    /// 列出所有 hwmon PWM 通道
+    ListPwm(ListPwmArgs),
+    /// 将风扇固定在指定 PWM，按 Ctrl+C 后恢复自动模式
+    Set(SetArgs),
+    /// 扫描 PWM 并记录风扇启停阈值
+    Calibrate(CalibrateArgs),
+    /// 显示 PWM 通道当前的转速与模式
+    Status(StatusArgs),
+}
+
+// 对所有子命令生效
+#[derive(clap::Args, Debug, Clone)]
+pub struct LogArgs {
+    /// 日志级别：off、error、warn、info、debug、trace，debug 会输出每次循环的温度和 PWM
+    #[arg(long, global = true, default_value_t = LevelFilter::Info)]
+    pub log_level: LevelFilter,
+    /// 日志格式
+    #[arg(long, global = true, value_enum, default_value_t = LogFormat::Text)]
+    pub log_format: LogFormat,
+}
+
+// 不带子命令时沿用旧的参数形式，等同于 run；--info 等同于 info
+#[derive(Parser, Debug)]
+#[command(args_conflicts_with_subcommands = true)]
+pub struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+    #[arg(long, hide = true)]
+    info: bool,
+    #[command(flatten)]
+    run: RunArgs,
+    #[command(flatten)]
+    pub log: LogArgs,
+}
+
+impl Args {
+    pub fn into_command(self) -> Command {
+        match self.command {
+            Some(command) => command,
+            None if self.info => Command::Info(InfoArgs {
+                root: self.run.target.root,
+                format: InfoFormat::Text,
+            }),
+            None => Command::Run(Box::new(self.run)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Command {
+        Args::try_parse_from(std::iter::once("gpu-fan-controller").chain(args.iter().copied()))
+            .unwrap()
+            .into_command()
+    }
+
+    #[test]
+    fn legacy_invocation_still_parses() {
+        let Command::Run(run) = parse(&["/sys/class/hwmon/hwmon2/pwm1", "--interval", "1"]) else {
+            panic!("expected run");
+        };
+        assert_eq!(run.target.pwm_path.as_deref(), Some("/sys/class/hwmon/hwmon2/pwm1"));
+        assert_eq!(run.interval, 1.0);
+
+        assert!(matches!(parse(&["--info"]), Command::Info(InfoArgs { format: InfoFormat::Text, .. })));
+        assert!(matches!(parse(&["run", "/dev/null"]), Command::Run(_)));
+        assert!(matches!(parse(&["list-pwm", "--hwmon-root", "/tmp"]), Command::ListPwm(_)));
+
+        let Command::Set(set) = parse(&["set", "--hwmon-name", "nct6775", "--pwm-channel", "2", "--value", "128"]) else {
+            panic!("expected set");
+        };
+        assert_eq!(set.value, 128);
+        assert!(Args::try_parse_from(["gpu-fan-controller", "info", "/dev/null"]).is_err());
+
+        let args = Args::try_parse_from(["gpu-fan-controller", "status", "/dev/null", "--log-level", "debug"]).unwrap();
+        assert_eq!(args.log.log_level, LevelFilter::Debug);
+        let args = Args::try_parse_from(["gpu-fan-controller", "/dev/null", "--log-format", "journald"]).unwrap();
+        assert_eq!(args.log.log_format, LogFormat::Journald);
+    }
+
+    #[test]
+    fn bool_flags_can_override_config() {
+        let run = |args: &[&str]| match parse(args) {
+            Command::Run(run) => run,
+            _ => panic!("expected run"),
+        };
+        // 未指定时交给配置文件决定
+        assert_eq!(run(&["/dev/null"]).exit_on_stall, None);
+        assert_eq!(run(&["/dev/null", "--exit-on-stall"]).exit_on_stall, Some(true));
+        assert_eq!(run(&["/dev/null", "--exit-on-stall=false"]).exit_on_stall, Some(false));
+        // 取值必须用 = 连接，不会吞掉后面的 PWM 路径
+        let flag_first = run(&["--exit-on-stall", "/dev/null"]);
+        assert_eq!((flag_first.exit_on_stall, flag_first.target.pwm_path.as_deref()), (Some(true), Some("/dev/null")));
+
+        assert_eq!(run(&["/dev/null"]).verify_writes, None);
+        assert_eq!(run(&["/dev/null", "--verify-writes"]).verify_writes, Some(true));
+        assert_eq!(run(&["/dev/null", "--verify-writes=false"]).verify_writes, Some(false));
+    }
+}
@@ -0,0 +1,312 @@
+use crate::{
+    curve::FanCurve,
+    fan::ControlMode,
+    filter::FilterKind,
+    gpu::GpuSelector,
+    hwmon::PwmTarget,
+    metric::MetricExpr,
+    pid::PidOverrides,
+    slew::SlewLimiter,
+};
+use serde::Deserialize;
+use std::fs;
+
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct RawConfig {
+    curve: Option<Vec<RawPoint>>,
+    hysteresis: Option<u32>,
+    metric: Option<String>,
+    mode: Option<ControlMode>,
+    filter: Option<String>,
+    ramp_up: Option<f64>,
+    ramp_down: Option<f64>,
+    stall_timeout: Option<f64>,
+    stall_pwm: Option<u8>,
+    on_stall: Option<String>,
+    exit_on_stall: Option<bool>,
+    failsafe_errors: Option<u32>,
+    failsafe_timeout: Option<f64>,
+    failsafe_pwm: Option<u8>,
+    mode_check_interval: Option<f64>,
+    verify_writes: Option<bool>,
+    verify_tolerance: Option<u8>,
+    exit_mode: Option<u8>,
+    #[serde(default)]
+    pid: PidOverrides,
+    #[serde(default)]
+    fan: Vec<RawFan>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct RawPoint {
+    temp: i64,
+    pwm: i64,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct RawFan {
+    pwm_path: Option<String>,
+    hwmon_name: Option<String>,
+    hwmon_device: Option<String>,
+    channel: Option<u32>,
+    curve: Option<Vec<RawPoint>>,
+    hysteresis: Option<u32>,
+    #[serde(default)]
+    min_pwm: i64,
+    gpu: Option<String>,
+    temp_input: Option<String>,
+    metric: Option<String>,
+    filter: Option<String>,
+    ramp_up: Option<f64>,
+    ramp_down: Option<f64>,
+    fan_input: Option<String>,
+    profile: Option<String>,
+    exit_mode: Option<u8>,
+}
+
+pub struct FanConfig {
+    pub target: PwmTarget,
+    pub curve: Option<FanCurve>,
+    pub hysteresis: Option<u32>,
+    pub min_pwm: u8,
+    pub gpu: Option<GpuSelector>,
+    pub temp_input: Option<String>,
+    pub metric: Option<MetricExpr>,
+    pub filter: Option<FilterKind>,
+    pub ramp_up: Option<f64>,
+    pub ramp_down: Option<f64>,
+    pub fan_input: Option<String>,
+    pub profile: Option<String>,
+    pub exit_mode: Option<u8>,
+}
+
+#[derive(Default)]
+pub struct Config {
+    pub curve: Option<FanCurve>,
+    pub hysteresis: Option<u32>,
+    pub metric: Option<MetricExpr>,
+    pub mode: Option<ControlMode>,
+    pub filter: Option<FilterKind>,
+    pub ramp_up: Option<f64>,
+    pub ramp_down: Option<f64>,
+    pub stall_timeout: Option<f64>,
+    pub stall_pwm: Option<u8>,
+    pub on_stall: Option<String>,
+    pub exit_on_stall: Option<bool>,
+    pub failsafe_errors: Option<u32>,
+    pub failsafe_timeout: Option<f64>,
+    pub failsafe_pwm: Option<u8>,
+    pub mode_check_interval: Option<f64>,
+    pub verify_writes: Option<bool>,
+    pub verify_tolerance: Option<u8>,
+    pub exit_mode: Option<u8>,
+    pub pid: PidOverrides,
+    pub fans: Vec<FanConfig>,
+}
+
+// 未指定时沿用默认曲线；显式写出的空曲线交给 FanCurve::new 报错
+fn parse_curve(points: Option<&[RawPoint]>) -> Result<Option<FanCurve>, String> {
+    let Some(points) = points else {
+        return Ok(None);
+    };
+    let points: Vec<(i64, i64)> = points.iter().map(|p| (p.temp, p.pwm)).collect();
+    FanCurve::new(&points).map(Some)
+}
+
+impl FanConfig {
+    fn from_raw(raw: RawFan) -> Result<Self, String> {
+        let min_pwm = u8::try_from(raw.min_pwm)
+            .map_err(|_| format!("min_pwm={} 必须在 0-255 之间", raw.min_pwm))?;
+        let gpu = raw.gpu.as_deref().map(str::parse).transpose()?;
+        if gpu.is_some() && raw.temp_input.is_some() {
+            return Err("gpu 与 temp_input 不能同时指定".to_string());
+        }
+        let metric = raw.metric.as_deref().map(str::parse).transpose()?;
+        let filter = raw.filter.as_deref().map(str::parse).transpose()?;
+        SlewLimiter::validate_rate("ramp_up", raw.ramp_up)?;
+        SlewLimiter::validate_rate("ramp_down", raw.ramp_down)?;
+
+        let target = match (raw.pwm_path, raw.hwmon_name, raw.hwmon_device, raw.channel) {
+            (Some(path), None, None, None) => PwmTarget::Path(path),
+            (None, Some(name), None, Some(channel)) => PwmTarget::Chip { name, channel },
+            (None, None, Some(device), Some(channel)) => PwmTarget::Device { device, channel },
+            _ => {
+                return Err(
+                    "必须指定 pwm_path，或 hwmon_name/hwmon_device 之一加上 channel".to_string(),
+                )
+            }
+        };
+
+        Ok(Self {
+            curve: parse_curve(raw.curve.as_deref())?,
+            target,
+            hysteresis: raw.hysteresis,
+            min_pwm,
+            gpu,
+            temp_input: raw.temp_input,
+            metric,
+            filter,
+            ramp_up: raw.ramp_up,
+            ramp_down: raw.ramp_down,
+            fan_input: raw.fan_input,
+            profile: raw.profile,
+            exit_mode: raw.exit_mode,
+        })
+    }
+}
+
+impl Config {
+    pub fn load(path: &str) -> Result<Self, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("无法读取配置文件 {}: {}", path, e))?;
+        let raw: RawConfig = toml::from_str(&text)
+            .map_err(|e| format!("配置文件 {} 格式错误: {}", path, e))?;
+
+        let curve = parse_curve(raw.curve.as_deref())
+            .map_err(|e| format!("配置文件 {} 无效: {}", path, e))?;
+        let metric = raw
+            .metric
+            .as_deref()
+            .map(str::parse)
+            .transpose()
+            .map_err(|e| format!("配置文件 {} 无效: {}", path, e))?;
+        let filter = raw
+            .filter
+            .as_deref()
+            .map(str::parse)
+            .transpose()
+            .map_err(|e| format!("配置文件 {} 无效: {}", path, e))?;
+        SlewLimiter::validate_rate("ramp_up", raw.ramp_up)
+            .and(SlewLimiter::validate_rate("ramp_down", raw.ramp_down))
+            .map_err(|e| format!("配置文件 {} 无效: {}", path, e))?;
+
+        let mut fans = Vec::with_capacity(raw.fan.len());
+        for (i, fan) in raw.fan.into_iter().enumerate() {
+            let fan = FanConfig::from_raw(fan)
+                .map_err(|e| format!("配置文件 {} 无效: 第 {} 个风扇: {}", path, i + 1, e))?;
+            fans.push(fan);
+        }
+
+        Ok(Self {
+            curve,
+            hysteresis: raw.hysteresis,
+            metric,
+            mode: raw.mode,
+            filter,
+            ramp_up: raw.ramp_up,
+            ramp_down: raw.ramp_down,
+            stall_timeout: raw.stall_timeout,
+            stall_pwm: raw.stall_pwm,
+            on_stall: raw.on_stall,
+            exit_on_stall: raw.exit_on_stall,
+            failsafe_errors: raw.failsafe_errors,
+            failsafe_timeout: raw.failsafe_timeout,
+            failsafe_pwm: raw.failsafe_pwm,
+            mode_check_interval: raw.mode_check_interval,
+            verify_writes: raw.verify_writes,
+            verify_tolerance: raw.verify_tolerance,
+            exit_mode: raw.exit_mode,
+            pid: raw.pid,
+            fans,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::TempDir;
+
+    fn load(text: &str) -> Result<Config, String> {
+        let dir = TempDir::new("config");
+        let path = dir.write("config.toml", text);
+        Config::load(&path.to_string_lossy())
+    }
+
+    #[test]
+    fn loads_curve_and_names_the_bad_point() {
+        let config = load("[[curve]]\ntemp = 30\npwm = 80\n\n[[curve]]\ntemp = 70\npwm = 255\n").unwrap();
+        let curve = config.curve.unwrap();
+        assert_eq!((curve.speed_at(30), curve.speed_at(50), curve.speed_at(90)), (80, 168, 255));
+
+        let err = load("[[curve]]\ntemp = 50\npwm = 100\n\n[[curve]]\ntemp = 40\npwm = 120\n").err().unwrap();
+        assert!(err.contains("曲线第 2 个点 (temp=40, pwm=120)"), "{}", err);
+
+        let err = load("[[curve]]\ntemp = 50\npwm = 300\n").err().unwrap();
+        assert!(err.contains("曲线第 1 个点 (temp=50, pwm=300): PWM 必须在 0-255 之间"), "{}", err);
+
+        // 未指定曲线使用默认值，显式的空曲线仍是错误
+        assert!(load("hysteresis = 2\n").unwrap().curve.is_none());
+        let err = load("curve = []\n").err().unwrap();
+        assert!(err.contains("风扇曲线至少需要一个点"), "{}", err);
+        let err = load("[[fan]]\npwm_path = \"/sys/class/hwmon/hwmon2/pwm1\"\ncurve = []\n").err().unwrap();
+        assert!(err.contains("第 1 个风扇: 风扇曲线至少需要一个点"), "{}", err);
+    }
+
+    #[test]
+    fn rejects_unknown_fields() {
+        let err = load("hysterisis = 3\n").err().unwrap();
+        assert!(err.contains("hysterisis"), "{}", err);
+
+        let err = load("[[curve]]\ntemp = 50\npwm = 100\nspeed = 3\n").err().unwrap();
+        assert!(err.contains("speed"), "{}", err);
+    }
+
+    #[test]
+    fn validates_fan_targets() {
+        let config = load(
+            "[[fan]]\npwm_path = \"/sys/class/hwmon/hwmon2/pwm1\"\n\n\
+             [[fan]]\nhwmon_name = \"nct6775\"\nchannel = 2\nmin_pwm = 60\n\n\
+             [[fan]]\nhwmon_device = \"/sys/devices/platform/nct6775.656\"\nchannel = 3\n",
+        )
+        .unwrap();
+        let targets: Vec<&PwmTarget> = config.fans.iter().map(|f| &f.target).collect();
+        assert_eq!(
+            targets,
+            [
+                &PwmTarget::Path("/sys/class/hwmon/hwmon2/pwm1".to_string()),
+                &PwmTarget::Chip {
+                    name: "nct6775".to_string(),
+                    channel: 2
+                },
+                &PwmTarget::Device {
+                    device: "/sys/devices/platform/nct6775.656".to_string(),
+                    channel: 3
+                },
+            ]
+        );
+        assert_eq!(config.fans[1].min_pwm, 60);
+
+        for fan in [
+            "pwm_path = \"/sys/class/hwmon/hwmon2/pwm1\"\nchannel = 1",
+            "pwm_path = \"/sys/class/hwmon/hwmon2/pwm1\"\nhwmon_name = \"nct6775\"\nchannel = 1",
+            "hwmon_name = \"nct6775\"",
+            "hwmon_name = \"nct6775\"\nhwmon_device = \"/sys/devices/x\"\nchannel = 1",
+            "channel = 1",
+            "min_pwm = 10",
+        ] {
+            let err = load(&format!("[[fan]]\n{}\n", fan)).err().unwrap();
+            assert!(err.contains("第 1 个风扇: 必须指定 pwm_path"), "{}: {}", fan, err);
+        }
+    }
+
+    #[test]
+    fn validates_fan_options() {
+        let fan = "[[fan]]\npwm_path = \"/sys/class/hwmon/hwmon2/pwm1\"\n";
+        let err = load(&format!("{}min_pwm = 256\n", fan)).err().unwrap();
+        assert!(err.contains("min_pwm=256 必须在 0-255 之间"), "{}", err);
+        let err = load(&format!("{}min_pwm = -1\n", fan)).err().unwrap();
+        assert!(err.contains("min_pwm=-1 必须在 0-255 之间"), "{}", err);
+
+        let err = load(&format!("{}gpu = \"0\"\ntemp_input = \"/sys/class/hwmon/hwmon1/temp1_input\"\n", fan))
+            .err()
+            .unwrap();
+        assert!(err.contains("gpu 与 temp_input 不能同时指定"), "{}", err);
+
+        let config = load(&format!("{}gpu = \"GPU-1234\"\n", fan)).unwrap();
+        assert_eq!(config.fans[0].gpu, Some(GpuSelector::Uuid("GPU-1234".to_string())));
+    }
+}
@@ -0,0 +1,161 @@
+#[derive(Debug, Clone)]
+pub struct FanCurve {
+    points: Vec<(u32, u8)>,
+}
+
+impl FanCurve {
+    pub fn new(points: &[(i64, i64)]) -> Result<Self, String> {
+        if points.is_empty() {
+            return Err("风扇曲线至少需要一个点".to_string());
+        }
+
+        let mut validated: Vec<(u32, u8)> = Vec::with_capacity(points.len());
+        for (i, &(temp, pwm)) in points.iter().enumerate() {
+            let point = format!("曲线第 {} 个点 (temp={}, pwm={})", i + 1, temp, pwm);
+            let temp = u32::try_from(temp)
+                .map_err(|_| format!("{}: 温度不能为负数", point))?;
+            let pwm = u8::try_from(pwm)
+                .map_err(|_| format!("{}: PWM 必须在 0-255 之间", point))?;
+            if let Some(&(prev_temp, prev_pwm)) = validated.last() {
+                if temp <= prev_temp {
+                    return Err(format!("{}: 温度必须严格递增 (上一个点为 {}°C)", point, prev_temp));
+                }
+                if pwm < prev_pwm {
+                    return Err(format!("{}: PWM 不能小于上一个点 ({})", point, prev_pwm));
+                }
+            }
+            validated.push((temp, pwm));
+        }
+
+        Ok(Self { points: validated })
+    }
+
+    #[inline(always)]
+    pub fn speed_at(&self, temp: u32) -> u8 {
+        let first = self.points[0];
+        if temp <= first.0 {
+            return first.1;
+        }
+
+        for window in self.points.windows(2) {
+            let (t0, p0) = window[0];
+            let (t1, p1) = window[1];
+            if temp <= t1 {
+                let span = t1 - t0;
+                let rise = (p1 - p0) as u32 * (temp - t0);
+                return p0 + ((rise + span / 2) / span) as u8;
+            }
+        }
+
+        self.points[self.points.len() - 1].1
+    }
+}
+
+pub struct Hysteresis {
+    band: u32,
+    trigger_temp: u32,
+    speed: Option<u8>,
+}
+
+impl Hysteresis {
+    pub fn new(band: u32) -> Self {
+        Self {
+            band,
+            trigger_temp: 0,
+            speed: None,
+        }
+    }
+
+    // 升速立即生效；降速需要温度比上次升速时低 band 度以上
+    #[inline(always)]
+    pub fn apply(&mut self, temp: u32, target: u8) -> u8 {
+        match self.speed {
+            Some(speed) if target == speed => speed,
+            Some(speed) if target < speed && temp.saturating_add(self.band) > self.trigger_temp => speed,
+            _ => {
+                self.trigger_temp = temp;
+                self.speed = Some(target);
+                target
+            }
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.speed = None;
+    }
+}
+
+impl Default for FanCurve {
+    fn default() -> Self {
+        Self {
+            points: vec![(25, 77), (59, 247), (60, 255)],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_curve_matches_builtin_ramp() {
+        let curve = FanCurve::default();
+        assert_eq!(curve.speed_at(0), 77);
+        assert_eq!(curve.speed_at(25), 77);
+        assert_eq!(curve.speed_at(26), 82);
+        assert_eq!(curve.speed_at(40), 152);
+        assert_eq!(curve.speed_at(59), 247);
+        assert_eq!(curve.speed_at(60), 255);
+        assert_eq!(curve.speed_at(150), 255);
+    }
+
+    #[test]
+    fn interpolates_between_points() {
+        let curve = FanCurve::new(&[(30, 60), (50, 100), (70, 100), (80, 255)]).unwrap();
+        assert_eq!(curve.speed_at(0), 60);
+        assert_eq!(curve.speed_at(30), 60);
+        assert_eq!(curve.speed_at(40), 80);
+        assert_eq!(curve.speed_at(50), 100);
+        assert_eq!(curve.speed_at(65), 100);
+        assert_eq!(curve.speed_at(70), 100);
+        assert_eq!(curve.speed_at(75), 178);
+        assert_eq!(curve.speed_at(80), 255);
+        assert_eq!(curve.speed_at(150), 255);
+    }
+
+    #[test]
+    fn single_point_is_constant() {
+        let curve = FanCurve::new(&[(50, 128)]).unwrap();
+        assert_eq!(curve.speed_at(0), 128);
+        assert_eq!(curve.speed_at(50), 128);
+        assert_eq!(curve.speed_at(150), 128);
+    }
+
+    #[test]
+    fn hysteresis_delays_ramp_down() {
+        let curve = FanCurve::default();
+        let mut hysteresis = Hysteresis::new(3);
+        let mut step = |temp| hysteresis.apply(temp, curve.speed_at(temp));
+
+        assert_eq!(step(40), 152);
+        assert_eq!(step(41), 157);
+        assert_eq!(step(40), 157);
+        assert_eq!(step(39), 157);
+        assert_eq!(step(38), 142);
+        assert_eq!(step(39), 147);
+        assert_eq!(step(37), 147);
+        assert_eq!(step(36), 132);
+    }
+
+    #[test]
+    fn rejects_invalid_points() {
+        assert!(FanCurve::new(&[]).is_err());
+        assert!(FanCurve::new(&[(-1, 77)]).is_err());
+        assert!(FanCurve::new(&[(30, 256)]).is_err());
+        assert!(FanCurve::new(&[(30, 80), (30, 90)]).is_err());
+        assert!(FanCurve::new(&[(30, 80), (40, 70)]).is_err());
+
+        let err = FanCurve::new(&[(30, 80), (20, 90)]).unwrap_err();
+        assert!(err.contains("第 2 个点"), "{}", err);
+    }
+}
@@ -0,0 +1,118 @@
+use nvml_wrapper::error::NvmlError;
+use std::io;
+use thiserror::Error;
+
+// io::Error 的 Display 会带上 (os error N)，启动失败时可以直接看到 errno
+#[derive(Debug, Error)]
+pub enum FanError {
+    #[error("无法初始化 NVML: {0}")]
+    NvmlInit(#[source] NvmlError),
+    #[error("GPU ({selector}) 不存在")]
+    GpuNotFound { selector: String },
+    #[error("GPU 标识无效 ({selector})")]
+    InvalidGpu { selector: String },
+    #[error("无法查找 GPU ({selector}): {source}")]
+    GpuLookup {
+        selector: String,
+        #[source]
+        source: NvmlError,
+    },
+    #[error("聚合使用的 GPU ({selector}) 不在 --gpu 列表中")]
+    AggregationGpu { selector: String },
+    #[error("GPU {gpu} 无法读取指标 {metric}，若该显卡不支持此指标，请改用 max(core, {metric}) 等形式，max 会忽略读取失败的项")]
+    MetricUnavailable { gpu: u32, metric: String },
+    #[error("{0}")]
+    Resolve(String),
+    #[error("{path} 不存在，该 PWM 通道不支持手动控制")]
+    MissingEnableFile { path: String },
+    #[error("没有权限访问 {path}: {source}，请以 root 身份运行")]
+    PermissionDenied {
+        path: String,
+        #[source]
+        source: io::Error,
+    },
+    #[error("无法打开 {path}: {source}")]
+    Open {
+        path: String,
+        #[source]
+        source: io::Error,
+    },
+    #[error("无法读取 {path}: {source}")]
+    Read {
+        path: String,
+        #[source]
+        source: io::Error,
+    },
+    #[error("无法解析 {path} 的内容: {content:?}")]
+    Parse { path: String, content: String },
+    #[error("无法写入 {value} 到 {path}: {source}")]
+    Write {
+        path: String,
+        value: u8,
+        #[source]
+        source: io::Error,
+    },
+    #[error("{path} 回读不一致 (连续 {count} 次): 写入 {written}，读回 {}", read.map_or_else(|| "失败".to_string(), |v| v.to_string()))]
+    Mismatch {
+        path: String,
+        written: u8,
+        read: Option<u8>,
+        count: u32,
+    },
+}
+
+impl FanError {
+    pub fn open(path: &str, source: io::Error) -> Self {
+        Self::io(path, source, |path, source| Self::Open { path, source })
+    }
+
+    pub fn read(path: &str, source: io::Error) -> Self {
+        Self::io(path, source, |path, source| Self::Read { path, source })
+    }
+
+    pub fn write(path: &str, value: u8, source: io::Error) -> Self {
+        Self::io(path, source, |path, source| Self::Write {
+            path,
+            value,
+            source,
+        })
+    }
+
+    fn io(path: &str, source: io::Error, other: impl FnOnce(String, io::Error) -> Self) -> Self {
+        let path = path.to_string();
+        match source.kind() {
+            io::ErrorKind::PermissionDenied => Self::PermissionDenied { path, source },
+            _ => other(path, source),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn io_errors_name_the_file_and_errno() {
+        let denied = FanError::write(
+            "/sys/class/hwmon/hwmon2/pwm1",
+            128,
+            io::Error::from_raw_os_error(13),
+        );
+        assert!(matches!(denied, FanError::PermissionDenied { .. }));
+        let message = denied.to_string();
+        assert!(
+            message.contains("/sys/class/hwmon/hwmon2/pwm1"),
+            "{}",
+            message
+        );
+        assert!(message.contains("os error 13"), "{}", message);
+
+        let gone = FanError::write(
+            "/sys/class/hwmon/hwmon2/pwm1",
+            128,
+            io::Error::from_raw_os_error(19),
+        );
+        assert!(matches!(gone, FanError::Write { value: 128, .. }));
+        assert!(gone.to_string().contains("os error 19"));
+    }
+}
@@ -0,0 +1,120 @@
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone)]
+pub struct FailsafeSettings {
+    pub errors: u32,
+    pub timeout: Option<Duration>,
+    pub pwm: u8,
+}
+
+impl Default for FailsafeSettings {
+    fn default() -> Self {
+        Self {
+            errors: 3,
+            timeout: None,
+            pwm: 255,
+        }
+    }
+}
+
+impl FailsafeSettings {
+    pub fn validate(self) -> Result<Self, String> {
+        if self.errors == 0 {
+            return Err("失效保护的连续错误次数必须大于 0".to_string());
+        }
+        Ok(self)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailsafeEvent {
+    Engaged,
+    Recovered,
+}
+
+// 连续读取失败达到次数，或距上次有效读数超过 timeout，即进入失效保护
+pub struct Failsafe {
+    settings: FailsafeSettings,
+    errors: u32,
+    last_valid: Option<Instant>,
+    active: bool,
+}
+
+impl Failsafe {
+    pub fn new(settings: FailsafeSettings) -> Self {
+        Self {
+            settings,
+            errors: 0,
+            last_valid: None,
+            active: false,
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    pub fn pwm(&self) -> u8 {
+        self.settings.pwm
+    }
+
+    pub fn errors(&self) -> u32 {
+        self.errors
+    }
+
+    pub fn record_ok(&mut self, now: Instant) -> Option<FailsafeEvent> {
+        self.errors = 0;
+        self.last_valid = Some(now);
+        if self.active {
+            self.active = false;
+            return Some(FailsafeEvent::Recovered);
+        }
+        None
+    }
+
+    pub fn record_error(&mut self, now: Instant) -> Option<FailsafeEvent> {
+        self.errors = self.errors.saturating_add(1);
+        // 启动后从未读到温度时，从第一次失败开始计时
+        let last_valid = *self.last_valid.get_or_insert(now);
+        let timed_out = self
+            .settings
+            .timeout
+            .is_some_and(|timeout| now.duration_since(last_valid) >= timeout);
+        if !self.active && (self.errors >= self.settings.errors || timed_out) {
+            self.active = true;
+            return Some(FailsafeEvent::Engaged);
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn engages_after_errors_or_timeout_and_recovers() {
+        let t0 = Instant::now();
+        let at = |s| t0 + Duration::from_secs(s);
+
+        let mut by_count = Failsafe::new(FailsafeSettings::default());
+        assert_eq!(by_count.record_ok(at(0)), None);
+        assert_eq!(by_count.record_error(at(1)), None);
+        assert_eq!(by_count.record_error(at(2)), None);
+        assert_eq!(by_count.record_error(at(3)), Some(FailsafeEvent::Engaged));
+        assert!(by_count.is_active());
+        assert_eq!(by_count.record_error(at(4)), None);
+        assert_eq!(by_count.record_ok(at(5)), Some(FailsafeEvent::Recovered));
+        assert_eq!(by_count.record_error(at(6)), None);
+
+        let mut by_time = Failsafe::new(FailsafeSettings {
+            errors: 100,
+            timeout: Some(Duration::from_secs(10)),
+            pwm: 200,
+        });
+        assert_eq!(by_time.record_error(at(0)), None);
+        assert_eq!(by_time.record_error(at(9)), None);
+        assert_eq!(by_time.record_error(at(10)), Some(FailsafeEvent::Engaged));
+        assert_eq!(by_time.pwm(), 200);
+    }
+}
@@ -0,0 +1,562 @@
+use crate::{
+    curve::{FanCurve, Hysteresis},
+    error::FanError,
+    failsafe::{Failsafe, FailsafeEvent, FailsafeSettings},
+    filter::{FilterKind, TempFilter},
+    hwmon::PwmTarget,
+    pid::PidController,
+    pwm::PwmOutput,
+    sensor::TemperatureSource,
+    slew::SlewLimiter,
+    stall::{StallDetector, StallEvent, StallSettings},
+};
+use clap::ValueEnum;
+use log::{debug, error, info, trace, warn};
+use serde::Deserialize;
+use std::{
+    cmp::Ordering,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ControlMode {
+    #[default]
+    Curve,
+    Pid,
+}
+
+pub enum Control {
+    Curve(FanCurve, Hysteresis),
+    Pid(PidController),
+}
+
+impl Control {
+    fn reset(&mut self) {
+        match self {
+            Self::Curve(_, hysteresis) => hysteresis.reset(),
+            Self::Pid(pid) => pid.reset(),
+        }
+    }
+}
+
+// 连续多少次回读不一致才报告为持续错误
+const VERIFY_MISMATCH_LIMIT: u32 = 3;
+
+fn within(actual: Option<u8>, expected: u8, tolerance: u8) -> bool {
+    actual.is_some_and(|v| v.abs_diff(expected) <= tolerance)
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ChannelOptions {
+    pub min_pwm: u8,
+    pub filter: Option<FilterKind>,
+    pub ramp_up: Option<f64>,
+    pub ramp_down: Option<f64>,
+    pub fan_input: Option<String>,
+    pub stall: Option<StallSettings>,
+    pub exit_mode: Option<u8>,
+    pub failsafe: FailsafeSettings,
+    pub mode_check: Option<Duration>,
+    pub target: Option<PwmTarget>,
+    pub hwmon_root: PathBuf,
+    pub verify: Option<u8>,
+}
+
+pub struct FanChannel {
+    source: Box<dyn TemperatureSource>,
+    output: PwmOutput,
+    target: Option<PwmTarget>,
+    hwmon_root: PathBuf,
+    last_temp: u32,
+    last_speed: u8,
+    control: Control,
+    min_pwm: u8,
+    filter: Option<TempFilter>,
+    slew: SlewLimiter,
+    stall: Option<StallDetector>,
+    failsafe: Failsafe,
+    mode_check: Option<Duration>,
+    last_mode_check: Option<Instant>,
+    mode_reasserts: u32,
+    verify: Option<u8>,
+    mismatches: u32,
+    last_tick: Option<Instant>,
+    gpu: Option<String>,
+}
+
+impl FanChannel {
+    pub fn new(source: Box<dyn TemperatureSource>, pwm_path: String, control: Control, options: ChannelOptions) -> Result<Self, FanError> {
+        let stall = match (options.stall, options.fan_input) {
+            (Some(settings), Some(fan_input)) => match StallDetector::new(fan_input, settings) {
+                Ok(detector) => Some(detector),
+                Err(e) => {
+                    warn!(event = "stall_detection_disabled", pwm_path; "{}，停转检测已禁用", e);
+                    None
+                }
+            },
+            (Some(_), None) => {
+                warn!(event = "stall_detection_disabled", pwm_path; "找不到对应的 fanN_input，停转检测已禁用");
+                None
+            }
+            _ => None,
+        };
+
+        let output = PwmOutput::new(pwm_path, options.exit_mode)?;
+        Ok(Self {
+            gpu: source.gpu(),
+            source,
+            output,
+            target: options.target,
+            hwmon_root: options.hwmon_root,
+            last_temp: 0,
+            last_speed: 0,
+            control,
+            min_pwm: options.min_pwm,
+            filter: options.filter.map(TempFilter::new),
+            slew: SlewLimiter::new(options.ramp_up, options.ramp_down),
+            stall,
+            failsafe: Failsafe::new(options.failsafe),
+            mode_check: options.mode_check,
+            last_mode_check: None,
+            mode_reasserts: 0,
+            verify: options.verify,
+            mismatches: 0,
+            last_tick: None,
+        })
+    }
+
+    #[inline(always)]
+    fn calculate_fan_speed(&mut self, temp: u32, dt: f64) -> u8 {
+        match self.control {
+            Control::Curve(ref curve, ref mut hysteresis) => {
+                let target = curve.speed_at(temp).max(self.min_pwm);
+                hysteresis.apply(temp, target)
+            }
+            Control::Pid(ref mut pid) => pid.update(temp as f64, dt).max(self.min_pwm),
+        }
+    }
+
+    #[inline(always)]
+    fn get_gpu_temp(&mut self) -> Option<u32> {
+        self.source.read_temp()
+    }
+
+    fn set_fan_speed(&mut self, speed: u8) -> Result<(), FanError> {
+        self.write_pwm(speed)?;
+        let Some(tolerance) = self.verify else {
+            return Ok(());
+        };
+
+        let mut actual = self.output.read_pwm().ok();
+        // 驱动在非手动模式下会忽略写入，重新切换后再试一次
+        if !within(actual, speed, tolerance)
+            && matches!(self.output.reassert_manual_mode(), Ok(Some(_)))
+            && self.write_pwm(speed).is_ok()
+        {
+            actual = self.output.read_pwm().ok();
+        }
+        if within(actual, speed, tolerance) {
+            self.mismatches = 0;
+            return Ok(());
+        }
+
+        self.mismatches += 1;
+        Err(FanError::Mismatch {
+            path: self.output.path().to_string(),
+            written: speed,
+            read: actual,
+            count: self.mismatches,
+        })
+    }
+
+    // 写入并记录失败原因；持续的回读不一致只在达到上限时报告一次
+    fn apply_speed(&mut self, speed: u8) -> bool {
+        match self.set_fan_speed(speed) {
+            Ok(()) => true,
+            Err(e @ FanError::Mismatch { count, .. }) => {
+                match count.cmp(&VERIFY_MISMATCH_LIMIT) {
+                    Ordering::Less => warn!(event = "verify_mismatch", pwm_path = self.output.path(), pwm = speed; "{}", e),
+                    Ordering::Equal => error!(event = "verify_mismatch", pwm_path = self.output.path(), pwm = speed; "{}，驱动可能忽略或改写了写入", e),
+                    Ordering::Greater => {}
+                }
+                false
+            }
+            Err(e) => {
+                error!(event = "pwm_write_failed", pwm_path = self.output.path(), pwm = speed; "{}", e);
+                false
+            }
+        }
+    }
+
+    fn write_pwm(&mut self, speed: u8) -> Result<(), FanError> {
+        if let Err(e) = self.output.set_fan_speed(speed) {
+            warn!(event = "hwmon_rebind", pwm_path = self.output.path(); "{}，重新查找 hwmon 通道", e);
+            self.rebind()?;
+            return self.output.set_fan_speed(speed);
+        }
+        Ok(())
+    }
+
+    // hwmon 重新枚举后 hwmonN 的编号可能改变，需要按原始目标重新解析
+    fn rebind(&mut self) -> Result<(), FanError> {
+        let path = match &self.target {
+            Some(target) => target.resolve(&self.hwmon_root).map_err(FanError::Resolve)?,
+            None => self.output.path().to_string(),
+        };
+        if path != self.output.path() {
+            info!(event = "hwmon_moved", pwm_path = self.output.path(); "hwmon 通道已移动到 {}", path);
+        }
+        self.output.reopen(path)
+    }
+
+    // 从挂起中恢复后，文件句柄、控制状态与计时都可能失效，全部重新初始化
+    pub fn reinitialize(&mut self) {
+        if let Err(e) = self.rebind() {
+            error!(event = "reinitialize_failed", pwm_path = self.output.path(); "重新初始化失败: {}", e);
+        }
+        self.control.reset();
+        if let Some(filter) = self.filter.as_mut() {
+            filter.reset();
+        }
+        self.last_tick = None;
+        self.last_mode_check = None;
+        if self.last_speed > 0 {
+            self.slew.reset_to(self.last_speed);
+            self.apply_speed(self.last_speed);
+        }
+    }
+
+    // 返回 true 表示风扇停转且要求退出
+    pub fn update(&mut self) -> bool {
+        let now = Instant::now();
+        let dt = self
+            .last_tick
+            .replace(now)
+            .map_or(0.0, |last| now.duration_since(last).as_secs_f64());
+        self.check_manual_mode(now);
+
+        if let Some(raw) = self.get_gpu_temp() {
+            if self.failsafe.record_ok(now) == Some(FailsafeEvent::Recovered) {
+                info!(event = "failsafe_released", pwm_path = self.output.path(), gpu = self.gpu; "温度读取已恢复，退出失效保护");
+            }
+            let temp = self.filter.as_mut().map_or(raw, |f| f.apply(raw));
+            let target = self.calculate_fan_speed(temp, dt);
+            let speed = self.slew.apply(target, dt);
+            trace!(
+                event = "pwm_tick", pwm_path = self.output.path(), gpu = self.gpu, temperature = temp, raw_temperature = raw, pwm = speed;
+                "温度: {}°C，目标速度: {} / 255", temp, speed
+            );
+            if (temp != self.last_temp || speed != self.last_speed) && self.apply_speed(speed) {
+                debug!(
+                    event = "pwm_update", pwm_path = self.output.path(), gpu = self.gpu, temperature = temp, raw_temperature = raw, pwm = speed;
+                    "温度: {}°C，风扇速度: {} / 255", temp, speed
+                );
+                self.last_temp = temp;
+                self.last_speed = speed;
+            }
+        } else {
+            self.handle_read_error(now);
+        }
+
+        self.check_stall(now)
+    }
+
+    fn check_manual_mode(&mut self, now: Instant) {
+        let Some(interval) = self.mode_check else {
+            return;
+        };
+        if self
+            .last_mode_check
+            .is_some_and(|last| now.duration_since(last) < interval)
+        {
+            return;
+        }
+        self.last_mode_check = Some(now);
+
+        let mode = match self.output.reassert_manual_mode() {
+            Ok(Some(mode)) => mode,
+            Ok(None) => return,
+            // 驱动重新加载后 hwmon 路径可能改变，温度稳定时不会有 PWM 写入触发重新查找
+            Err(e) => {
+                error!(event = "mode_check_failed", pwm_path = self.output.path(); "{}，重新查找 hwmon 通道", e);
+                match self.rebind() {
+                    // 新通道的 PWM 值未知，立即写回当前速度
+                    Ok(()) if self.last_speed > 0 => {
+                        self.apply_speed(self.last_speed);
+                    }
+                    Ok(()) => {}
+                    Err(e) => error!(event = "hwmon_rebind_failed", pwm_path = self.output.path(); "{}", e),
+                }
+                return;
+            }
+        };
+        self.mode_reasserts += 1;
+        warn!(
+            event = "mode_reasserted", pwm_path = self.output.path(), mode, reasserts = self.mode_reasserts;
+            "pwm_enable 被改为 {}，已重新切换为手动模式 (累计 {} 次)", mode, self.mode_reasserts
+        );
+        // 接管期间的 PWM 值可能已被固件改写
+        if self.last_speed > 0 {
+            self.apply_speed(self.last_speed);
+        }
+    }
+
+    // 读取失败时先保持当前速度，达到失效保护条件后切换到失效保护速度
+    fn handle_read_error(&mut self, now: Instant) {
+        if self.failsafe.record_error(now) == Some(FailsafeEvent::Engaged) {
+            error!(
+                event = "failsafe_engaged", pwm_path = self.output.path(), gpu = self.gpu, pwm = self.failsafe.pwm();
+                "连续 {} 次无法读取温度，进入失效保护，风扇速度 {}", self.failsafe.errors(), self.failsafe.pwm()
+            );
+        }
+        if !self.failsafe.is_active() {
+            warn!(
+                event = "temp_read_failed", pwm_path = self.output.path(), gpu = self.gpu, pwm = self.last_speed;
+                "无法读取温度 (第 {} 次)，保持当前速度 {}", self.failsafe.errors(), self.last_speed
+            );
+            return;
+        }
+
+        let speed = self.failsafe.pwm();
+        if self.last_speed != speed && self.apply_speed(speed) {
+            self.last_speed = speed;
+            self.slew.reset_to(speed);
+            self.control.reset();
+            if let Some(filter) = self.filter.as_mut() {
+                filter.reset();
+            }
+        }
+    }
+
+    fn check_stall(&mut self, now: Instant) -> bool {
+        let Some(ref mut stall) = self.stall else {
+            return false;
+        };
+        let rpm = stall.read_rpm();
+        match stall.check(self.last_speed, rpm, now) {
+            Some(StallEvent::Stalled) => {
+                error!(
+                    event = "fan_stalled", pwm_path = self.output.path(), pwm = self.last_speed, rpm = 0;
+                    "风扇故障: PWM 为 {} 时转速持续为 0 ({})", self.last_speed, stall.path()
+                );
+                stall.run_hook(self.output.path(), self.last_speed, StallEvent::Stalled);
+                stall.exit_on_stall()
+            }
+            Some(StallEvent::Recovered) => {
+                info!(event = "fan_recovered", pwm_path = self.output.path(), pwm = self.last_speed, rpm; "风扇已恢复转动: {} RPM", rpm.unwrap_or(0));
+                stall.run_hook(self.output.path(), self.last_speed, StallEvent::Recovered);
+                false
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::{fake_pwm, TempDir};
+    use crate::sensor::MockSource;
+    use std::fs;
+
+    #[test]
+    fn update_follows_scripted_temperatures() {
+        let (dir, pwm_path) = fake_pwm("update", "0", "2");
+        let readings = [Some(20), Some(40), Some(38), None, Some(70)];
+        let mut channel = FanChannel::new(
+            Box::new(MockSource::new(&readings)),
+            pwm_path.clone(),
+            Control::Curve(FanCurve::default(), Hysteresis::new(3)),
+            ChannelOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(fs::read_to_string(dir.join("pwm1_enable")).unwrap(), "1");
+
+        channel.update();
+        assert_eq!(channel.last_speed, 77);
+        assert_eq!(fs::read_to_string(&pwm_path).unwrap(), "77");
+
+        channel.update();
+        assert_eq!(channel.last_speed, 152);
+        channel.update();
+        assert_eq!(channel.last_speed, 152);
+        channel.update();
+        assert_eq!(channel.last_speed, 152);
+        channel.update();
+        assert_eq!(channel.last_speed, 255);
+        assert_eq!(fs::read_to_string(&pwm_path).unwrap(), "255");
+
+        drop(channel);
+        assert_eq!(fs::read_to_string(dir.join("pwm1_enable")).unwrap(), "2");
+    }
+
+    #[test]
+    fn reasserts_manual_mode_when_firmware_takes_over() {
+        let (dir, pwm_path) = fake_pwm("reassert", "0", "2");
+        let mut channel = FanChannel::new(
+            Box::new(MockSource::new(&[Some(40), Some(40), Some(40)])),
+            pwm_path.clone(),
+            Control::Curve(FanCurve::default(), Hysteresis::new(0)),
+            ChannelOptions {
+                mode_check: Some(Duration::ZERO),
+                ..ChannelOptions::default()
+            },
+        )
+        .unwrap();
+
+        channel.update();
+        assert_eq!(channel.mode_reasserts, 0);
+        fs::write(dir.join("pwm1_enable"), "2").unwrap();
+        fs::write(&pwm_path, "000").unwrap();
+        channel.update();
+        assert_eq!(channel.mode_reasserts, 1);
+        assert_eq!(fs::read_to_string(dir.join("pwm1_enable")).unwrap(), "1");
+        assert_eq!(fs::read_to_string(&pwm_path).unwrap(), "152");
+        channel.update();
+        assert_eq!(channel.mode_reasserts, 1);
+
+        drop(channel);
+    }
+
+    #[test]
+    fn reinitialize_follows_renumbered_hwmon() {
+        let root = TempDir::new("rebind");
+        fs::create_dir_all(root.join("hwmon3")).unwrap();
+        fs::write(root.join("hwmon3/name"), "nct6775\n").unwrap();
+        fs::write(root.join("hwmon3/pwm2"), "0").unwrap();
+        fs::write(root.join("hwmon3/pwm2_enable"), "2").unwrap();
+
+        let target = PwmTarget::Chip {
+            name: "nct6775".to_string(),
+            channel: 2,
+        };
+        let mut channel = FanChannel::new(
+            Box::new(MockSource::new(&[Some(40), Some(40)])),
+            target.resolve(&root).unwrap(),
+            Control::Curve(FanCurve::default(), Hysteresis::new(0)),
+            ChannelOptions {
+                target: Some(target),
+                hwmon_root: root.to_path_buf(),
+                ..ChannelOptions::default()
+            },
+        )
+        .unwrap();
+        channel.update();
+
+        // 模拟驱动重新加载：芯片以新编号重新出现，且回到自动模式
+        fs::rename(root.join("hwmon3"), root.join("hwmon7")).unwrap();
+        fs::write(root.join("hwmon7/pwm2_enable"), "2").unwrap();
+        channel.reinitialize();
+        assert_eq!(channel.output.path(), root.join("hwmon7/pwm2").to_string_lossy());
+        assert_eq!(fs::read_to_string(root.join("hwmon7/pwm2_enable")).unwrap(), "1");
+        assert_eq!(fs::read_to_string(root.join("hwmon7/pwm2")).unwrap(), "152");
+
+        drop(channel);
+    }
+
+    #[test]
+    fn mode_check_failure_rebinds_without_pwm_writes() {
+        let root = TempDir::new("mode-rebind");
+        for dir in ["hwmon3", "hwmon7"] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+            fs::write(root.join(dir).join("pwm2"), "0").unwrap();
+            fs::write(root.join(dir).join("pwm2_enable"), "2").unwrap();
+        }
+        fs::write(root.join("hwmon3/name"), "nct6775\n").unwrap();
+
+        let target = PwmTarget::Chip {
+            name: "nct6775".to_string(),
+            channel: 2,
+        };
+        let mut channel = FanChannel::new(
+            Box::new(MockSource::new(&[Some(40), Some(40)])),
+            target.resolve(&root).unwrap(),
+            Control::Curve(FanCurve::default(), Hysteresis::new(0)),
+            ChannelOptions {
+                target: Some(target),
+                hwmon_root: root.to_path_buf(),
+                mode_check: Some(Duration::ZERO),
+                ..ChannelOptions::default()
+            },
+        )
+        .unwrap();
+        channel.update();
+
+        // 旧属性失效，芯片以新编号出现；温度不变，不会有 PWM 写入
+        fs::remove_file(root.join("hwmon3/name")).unwrap();
+        fs::write(root.join("hwmon7/name"), "nct6775\n").unwrap();
+        fs::write(root.join("hwmon3/pwm2_enable"), "-").unwrap();
+        channel.update();
+        assert_eq!(channel.output.path(), root.join("hwmon7/pwm2").to_string_lossy());
+        assert_eq!(fs::read_to_string(root.join("hwmon7/pwm2_enable")).unwrap(), "1");
+        assert_eq!(fs::read_to_string(root.join("hwmon7/pwm2")).unwrap(), "152");
+
+        drop(channel);
+    }
+
+    #[test]
+    fn verify_rejects_values_the_driver_changed() {
+        let (_dir, pwm_path) = fake_pwm("verify", "0", "2");
+        let mut channel = FanChannel::new(
+            Box::new(MockSource::new(&[])),
+            pwm_path.clone(),
+            Control::Curve(FanCurve::default(), Hysteresis::new(0)),
+            ChannelOptions {
+                verify: Some(2),
+                ..ChannelOptions::default()
+            },
+        )
+        .unwrap();
+
+        channel.set_fan_speed(128).unwrap();
+        assert_eq!(channel.mismatches, 0);
+        assert!(within(Some(126), 128, 2));
+        assert!(!within(Some(125), 128, 2));
+        assert!(!within(None, 128, 2));
+
+        // 普通文件写入不会截断，"90" 上写 "5" 会读回 50，相当于驱动改写了写入的值
+        for n in 1..=VERIFY_MISMATCH_LIMIT + 1 {
+            fs::write(&pwm_path, "90").unwrap();
+            let err = channel.set_fan_speed(5).unwrap_err();
+            assert!(matches!(err, FanError::Mismatch { written: 5, read: Some(50), count, .. } if count == n));
+        }
+        fs::write(&pwm_path, "0").unwrap();
+        channel.set_fan_speed(6).unwrap();
+        assert_eq!(channel.mismatches, 0);
+
+        drop(channel);
+    }
+
+    #[test]
+    fn failsafe_engages_and_releases() {
+        let (dir, _) = fake_pwm("failsafe", "0", "2");
+        let readings = [Some(40), None, None, None, Some(40)];
+        let mut channel = FanChannel::new(
+            Box::new(MockSource::new(&readings)),
+            dir.join("pwm1").to_string_lossy().into_owned(),
+            Control::Curve(FanCurve::default(), Hysteresis::new(0)),
+            ChannelOptions {
+                failsafe: FailsafeSettings {
+                    errors: 2,
+                    timeout: None,
+                    pwm: 230,
+                },
+                ..ChannelOptions::default()
+            },
+        )
+        .unwrap();
+
+        let speeds: Vec<u8> = (0..readings.len())
+            .map(|_| {
+                channel.update();
+                channel.last_speed
+            })
+            .collect();
+        assert_eq!(speeds, [152, 152, 230, 230, 152]);
+        assert!(!channel.failsafe.is_active());
+
+        drop(channel);
+    }
+}
@@ -0,0 +1,108 @@
+use std::{collections::VecDeque, fmt, str::FromStr};
+
+const MAX_SMA_SAMPLES: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FilterKind {
+    Sma(usize),
+    Ema(f64),
+}
+
+impl FromStr for FilterKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, param) = s
+            .trim()
+            .split_once(':')
+            .ok_or_else(|| format!("无效的滤波器: {} (应为 sma:<采样数> 或 ema:<alpha>)", s))?;
+        match kind {
+            "sma" => match param.parse() {
+                Ok(n) if (1..=MAX_SMA_SAMPLES).contains(&n) => Ok(Self::Sma(n)),
+                _ => Err(format!("sma 采样数必须在 1-{} 之间: {}", MAX_SMA_SAMPLES, param)),
+            },
+            "ema" => match param.parse() {
+                Ok(alpha) if alpha > 0.0 && alpha <= 1.0 => Ok(Self::Ema(alpha)),
+                _ => Err(format!("ema 的 alpha 必须在 (0, 1] 之间: {}", param)),
+            },
+            _ => Err(format!("未知的滤波器类型: {} (可选 sma、ema)", kind)),
+        }
+    }
+}
+
+impl fmt::Display for FilterKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Sma(n) => write!(f, "sma:{}", n),
+            Self::Ema(alpha) => write!(f, "ema:{}", alpha),
+        }
+    }
+}
+
+pub enum TempFilter {
+    Sma { samples: VecDeque<u32>, size: usize },
+    Ema { alpha: f64, value: Option<f64> },
+}
+
+impl TempFilter {
+    pub fn new(kind: FilterKind) -> Self {
+        match kind {
+            FilterKind::Sma(size) => Self::Sma {
+                samples: VecDeque::new(),
+                size,
+            },
+            FilterKind::Ema(alpha) => Self::Ema { alpha, value: None },
+        }
+    }
+
+    pub fn apply(&mut self, raw: u32) -> u32 {
+        match self {
+            Self::Sma { samples, size } => {
+                if samples.len() == *size {
+                    samples.pop_front();
+                }
+                samples.push_back(raw);
+                let n = samples.len() as u32;
+                (samples.iter().sum::<u32>() + n / 2) / n
+            }
+            Self::Ema { alpha, value } => {
+                let next = match *value {
+                    Some(v) => v + *alpha * (raw as f64 - v),
+                    None => raw as f64,
+                };
+                *value = Some(next);
+                next.round() as u32
+            }
+        }
+    }
+
+    pub fn reset(&mut self) {
+        match self {
+            Self::Sma { samples, .. } => samples.clear(),
+            Self::Ema { value, .. } => *value = None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn smooths_temperature_spikes() {
+        let mut sma = TempFilter::new("sma:3".parse().unwrap());
+        let out: Vec<u32> = [50, 50, 62, 50, 50].iter().map(|&t| sma.apply(t)).collect();
+        assert_eq!(out, [50, 50, 54, 54, 54]);
+
+        let mut ema = TempFilter::new("ema:0.5".parse().unwrap());
+        let out: Vec<u32> = [50, 60, 60, 60].iter().map(|&t| ema.apply(t)).collect();
+        assert_eq!(out, [50, 55, 58, 59]);
+
+        assert!("sma:0".parse::<FilterKind>().is_err());
+        assert_eq!("sma:1000".parse(), Ok(FilterKind::Sma(1000)));
+        let err = "sma:100000000000000".parse::<FilterKind>().unwrap_err();
+        assert!(err.contains("100000000000000"), "{}", err);
+        assert!("ema:1.5".parse::<FilterKind>().is_err());
+        assert!("median:3".parse::<FilterKind>().is_err());
+    }
+}
@@ -0,0 +1,266 @@
+use crate::{error::FanError, metric::MetricExpr};
+use log::{debug, info, warn};
+use nvml_wrapper::{Nvml, error::NvmlError};
+use std::{fmt, str::FromStr};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GpuSelector {
+    Index(u32),
+    Uuid(String),
+    PciBusId(String),
+}
+
+impl FromStr for GpuSelector {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Err("GPU 标识不能为空".to_string());
+        }
+        if let Ok(index) = s.parse() {
+            Ok(Self::Index(index))
+        } else if s.starts_with("GPU-") || s.starts_with("MIG-") {
+            Ok(Self::Uuid(s.to_string()))
+        } else if s.contains(':') {
+            Ok(Self::PciBusId(s.to_string()))
+        } else {
+            Err(format!("无法识别的 GPU 标识: {} (应为索引、UUID 或 PCI 总线 ID)", s))
+        }
+    }
+}
+
+impl fmt::Display for GpuSelector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Index(index) => write!(f, "索引 {}", index),
+            Self::Uuid(uuid) => write!(f, "UUID {}", uuid),
+            Self::PciBusId(bus_id) => write!(f, "PCI {}", bus_id),
+        }
+    }
+}
+
+impl GpuSelector {
+    pub fn resolve(&self, nvml: &Nvml) -> Result<u32, FanError> {
+        match self {
+            Self::Index(index) => nvml.device_by_index(*index),
+            Self::Uuid(uuid) => nvml.device_by_uuid(uuid.as_str()),
+            Self::PciBusId(bus_id) => nvml.device_by_pci_bus_id(bus_id.as_str()),
+        }
+        .and_then(|device| device.index())
+        .map_err(|e| {
+            let selector = self.to_string();
+            match e {
+                NvmlError::NotFound => FanError::GpuNotFound { selector },
+                NvmlError::InvalidArg => FanError::InvalidGpu { selector },
+                source => FanError::GpuLookup { selector, source },
+            }
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Aggregation {
+    Max,
+    Average,
+    Gpu(GpuSelector),
+}
+
+impl FromStr for Aggregation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "max" => Ok(Self::Max),
+            "average" | "avg" => Ok(Self::Average),
+            other => other.parse().map(Self::Gpu),
+        }
+    }
+}
+
+pub enum ResolvedAggregation {
+    Max,
+    Average,
+    Single(usize),
+}
+
+impl ResolvedAggregation {
+    // 任一成员读取失败时返回其位置，而不是忽略它：
+    // 否则最热的 GPU 掉线后风扇会跟随较冷的 GPU，失效保护也不会触发
+    pub fn combine(&self, count: usize, mut read: impl FnMut(usize) -> Option<u32>) -> Result<u32, usize> {
+        if let Self::Single(pos) = *self {
+            return read(pos).ok_or(pos);
+        }
+        let (mut max, mut sum) = (0, 0);
+        for pos in 0..count {
+            let temp = read(pos).ok_or(pos)?;
+            max = max.max(temp);
+            sum += temp;
+        }
+        let n = count.max(1) as u32;
+        Ok(match self {
+            Self::Average => (sum + n / 2) / n,
+            _ => max,
+        })
+    }
+}
+
+// 只在成员从正常变为失败、以及恢复时各记录一次，避免每次循环都写日志
+#[derive(Default)]
+pub struct MemberHealth {
+    failed: Option<usize>,
+}
+
+impl MemberHealth {
+    pub fn track(&mut self, result: Result<u32, usize>, name: impl Fn(usize) -> String) -> Option<u32> {
+        match result {
+            Ok(temp) => {
+                if let Some(pos) = self.failed.take() {
+                    info!(event = "gpu_read_recovered", gpu = name(pos); "GPU 温度读取已恢复: {}", name(pos));
+                }
+                Some(temp)
+            }
+            Err(pos) => {
+                if self.failed.replace(pos) == Some(pos) {
+                    debug!(event = "gpu_read_failed", gpu = name(pos); "无法读取 GPU 温度: {}", name(pos));
+                } else {
+                    warn!(event = "gpu_read_failed", gpu = name(pos); "无法读取 GPU 温度: {}", name(pos));
+                }
+                None
+            }
+        }
+    }
+}
+
+pub struct GpuSet {
+    indices: Vec<u32>,
+    aggregation: ResolvedAggregation,
+    metric: MetricExpr,
+    health: MemberHealth,
+}
+
+impl GpuSet {
+    pub fn resolve(
+        nvml: &Nvml,
+        selectors: &[GpuSelector],
+        aggregation: &Aggregation,
+        metric: &MetricExpr,
+    ) -> Result<Self, FanError> {
+        let mut indices = Vec::with_capacity(selectors.len());
+        for selector in selectors {
+            let index = selector.resolve(nvml)?;
+            if !indices.contains(&index) {
+                indices.push(index);
+            }
+        }
+        if indices.is_empty() {
+            indices.push(GpuSelector::Index(0).resolve(nvml)?);
+        }
+
+        let aggregation = match aggregation {
+            Aggregation::Max => ResolvedAggregation::Max,
+            Aggregation::Average => ResolvedAggregation::Average,
+            Aggregation::Gpu(selector) => {
+                let index = selector.resolve(nvml)?;
+                let pos = indices
+                    .iter()
+                    .position(|&i| i == index)
+                    .ok_or_else(|| FanError::AggregationGpu {
+                        selector: selector.to_string(),
+                    })?;
+                ResolvedAggregation::Single(pos)
+            }
+        };
+
+        let set = Self {
+            indices,
+            aggregation,
+            metric: metric.clone(),
+            health: MemberHealth::default(),
+        };
+        // 任一 GPU 读取失败都会让整个读数失败，启动时就拒绝而不是一直停在失效保护
+        if let Some(&gpu) = set.indices.iter().find(|&&i| set.read_temp(nvml, i).is_none()) {
+            return Err(FanError::MetricUnavailable {
+                gpu,
+                metric: set.metric.to_string(),
+            });
+        }
+        Ok(set)
+    }
+
+    pub fn indices(&self) -> &[u32] {
+        &self.indices
+    }
+
+    pub fn metric(&self) -> &MetricExpr {
+        &self.metric
+    }
+
+    #[inline(always)]
+    fn read_temp(&self, nvml: &Nvml, index: u32) -> Option<u32> {
+        self.metric.eval_device(&nvml.device_by_index(index).ok()?)
+    }
+
+    pub fn temperature(&mut self, nvml: &Nvml) -> Option<u32> {
+        let result = self
+            .aggregation
+            .combine(self.indices.len(), |pos| self.read_temp(nvml, self.indices[pos]));
+        let indices = &self.indices;
+        self.health.track(result, |pos| indices[pos].to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_selectors_and_aggregation() {
+        assert_eq!("1".parse(), Ok(GpuSelector::Index(1)));
+        assert_eq!(
+            " GPU-5f1c2a3b-0000-1111-2222-333344445555 ".parse(),
+            Ok(GpuSelector::Uuid("GPU-5f1c2a3b-0000-1111-2222-333344445555".to_string()))
+        );
+        assert_eq!(
+            "MIG-0a1b2c3d-4e5f-6789-abcd-ef0123456789".parse(),
+            Ok(GpuSelector::Uuid("MIG-0a1b2c3d-4e5f-6789-abcd-ef0123456789".to_string()))
+        );
+        assert_eq!(
+            "00000000:01:00.0".parse(),
+            Ok(GpuSelector::PciBusId("00000000:01:00.0".to_string()))
+        );
+        assert!("".parse::<GpuSelector>().is_err());
+        assert!("hottest".parse::<GpuSelector>().is_err());
+        assert!("-1".parse::<GpuSelector>().is_err());
+
+        assert_eq!("max".parse(), Ok(Aggregation::Max));
+        assert_eq!("avg".parse(), Ok(Aggregation::Average));
+        assert_eq!("average".parse(), Ok(Aggregation::Average));
+        assert_eq!("2".parse(), Ok(Aggregation::Gpu(GpuSelector::Index(2))));
+        assert!("median".parse::<Aggregation>().is_err());
+    }
+
+    #[test]
+    fn missing_member_fails_the_whole_reading() {
+        let temps = [Some(60), Some(80), Some(71)];
+        let read = |pos: usize| temps[pos];
+        assert_eq!(ResolvedAggregation::Max.combine(3, read), Ok(80));
+        assert_eq!(ResolvedAggregation::Average.combine(3, read), Ok(70));
+        assert_eq!(ResolvedAggregation::Single(2).combine(3, read), Ok(71));
+
+        let temps = [Some(60), None, Some(71)];
+        let read = |pos: usize| temps[pos];
+        assert_eq!(ResolvedAggregation::Max.combine(3, read), Err(1));
+        assert_eq!(ResolvedAggregation::Average.combine(3, read), Err(1));
+        // 单个 GPU 模式只读取选中的那一个
+        assert_eq!(ResolvedAggregation::Single(0).combine(3, read), Ok(60));
+
+        let mut health = MemberHealth::default();
+        let name = |pos: usize| pos.to_string();
+        assert_eq!(health.track(Err(1), name), None);
+        assert_eq!(health.failed, Some(1));
+        assert_eq!(health.track(Err(1), name), None);
+        assert_eq!(health.track(Ok(70), name), Some(70));
+        assert_eq!(health.failed, None);
+    }
+}
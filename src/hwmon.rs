@@ -0,0 +1,351 @@
+use std::{
+    fmt,
+    fs::{self, File, OpenOptions},
+    io::{Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+pub const SYSFS_HWMON: &str = "/sys/class/hwmon";
+
+// 缓存文件句柄的 sysfs 数值属性，每次读取都从头开始
+pub struct SysfsValue {
+    path: String,
+    file: Option<File>,
+    content_buf: String,
+}
+
+impl SysfsValue {
+    pub fn new(path: String) -> Self {
+        Self {
+            path,
+            file: None,
+            content_buf: String::with_capacity(16),
+        }
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn read<T: FromStr>(&mut self) -> Option<T> {
+        if self.file.is_none() {
+            self.file = File::open(&self.path).ok();
+        }
+        let file = self.file.as_mut()?;
+        self.content_buf.clear();
+        // 读取失败时丢弃句柄，驱动重新加载后下次读取会重新打开
+        if file.seek(SeekFrom::Start(0)).is_err() || file.read_to_string(&mut self.content_buf).is_err() {
+            self.file = None;
+            return None;
+        }
+        self.content_buf.trim().parse().ok()
+    }
+}
+
+// pwmN 对应的转速文件通常是同目录下的 fanN_input
+pub fn fan_input_for(pwm_path: &str) -> Option<String> {
+    let path = Path::new(pwm_path);
+    let channel = path.file_name()?.to_str()?.strip_prefix("pwm")?;
+    if channel.is_empty() || !channel.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let input = path.with_file_name(format!("fan{}_input", channel));
+    input.exists().then(|| input.to_string_lossy().into_owned())
+}
+
+// pwmN_enable: 0 全速，1 手动，2 及以上为驱动/固件自动控制
+pub fn enable_mode_name(mode: u8) -> &'static str {
+    match mode {
+        0 => "全速",
+        1 => "手动",
+        _ => "自动",
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PwmState {
+    pub pwm: Option<u8>,
+    pub enable: Option<u8>,
+    pub fan_input: Option<String>,
+    pub rpm: Option<u32>,
+    pub writable: bool,
+}
+
+impl PwmState {
+    pub fn read(pwm_path: &str) -> Self {
+        let fan_input = fan_input_for(pwm_path);
+        Self {
+            pwm: SysfsValue::new(pwm_path.to_string()).read(),
+            enable: SysfsValue::new(format!("{}_enable", pwm_path)).read(),
+            rpm: fan_input.clone().and_then(|p| SysfsValue::new(p).read()),
+            fan_input,
+            // 只打开不写入，不会改变通道状态
+            writable: OpenOptions::new().write(true).open(pwm_path).is_ok(),
+        }
+    }
+}
+
+impl fmt::Display for PwmState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.pwm {
+            Some(pwm) => write!(f, "PWM {:>3} / 255", pwm)?,
+            None => write!(f, "PWM 读取失败")?,
+        }
+        match self.enable {
+            Some(mode) => write!(f, "，模式 {} ({})", mode, enable_mode_name(mode))?,
+            None => write!(f, "，无 pwm_enable")?,
+        }
+        match (&self.fan_input, self.rpm) {
+            (Some(_), Some(rpm)) => write!(f, "，转速 {} RPM", rpm)?,
+            (Some(_), None) => write!(f, "，转速读取失败")?,
+            (None, _) => {}
+        }
+        write!(f, "，{}", if self.writable { "可写" } else { "只读" })
+    }
+}
+
+// 按通道号排序的 pwmN 文件
+pub fn pwm_channels(hwmon_dir: &Path) -> Vec<(u32, PathBuf)> {
+    let mut channels: Vec<(u32, PathBuf)> = fs::read_dir(hwmon_dir)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .filter_map(|e| {
+                    let channel = e.file_name().to_str()?.strip_prefix("pwm")?.parse().ok()?;
+                    Some((channel, e.path()))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    channels.sort_by_key(|(channel, _)| *channel);
+    channels
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PwmChannel {
+    pub channel: u32,
+    pub path: String,
+    pub state: PwmState,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HwmonChip {
+    pub dir: PathBuf,
+    pub name: Option<String>,
+    pub channels: Vec<PwmChannel>,
+}
+
+pub fn list_chips(root: &Path) -> Vec<HwmonChip> {
+    list_hwmon_dirs(root)
+        .into_iter()
+        .map(|dir| {
+            let channels = pwm_channels(&dir)
+                .into_iter()
+                .map(|(channel, path)| {
+                    let path = path.to_string_lossy().into_owned();
+                    PwmChannel {
+                        channel,
+                        state: PwmState::read(&path),
+                        path,
+                    }
+                })
+                .collect();
+            HwmonChip {
+                name: read_name(&dir),
+                dir,
+                channels,
+            }
+        })
+        .collect()
+}
+
+// 按规范化后的路径比较，返回第一对重复的路径
+pub fn find_duplicate_path<'a>(paths: impl IntoIterator<Item = &'a str>) -> Option<(&'a str, &'a str)> {
+    let mut seen: Vec<(PathBuf, &str)> = Vec::new();
+    for path in paths {
+        let canonical = fs::canonicalize(path).unwrap_or_else(|_| PathBuf::from(path));
+        if let Some((_, first)) = seen.iter().find(|(p, _)| *p == canonical) {
+            return Some((first, path));
+        }
+        seen.push((canonical, path));
+    }
+    None
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PwmTarget {
+    Path(String),
+    Chip { name: String, channel: u32 },
+    Device { device: String, channel: u32 },
+}
+
+impl fmt::Display for PwmTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Path(path) => write!(f, "{}", path),
+            Self::Chip { name, channel } => write!(f, "{}/pwm{}", name, channel),
+            Self::Device { device, channel } => write!(f, "{}/pwm{}", device, channel),
+        }
+    }
+}
+
+pub fn read_name(hwmon_dir: &Path) -> Option<String> {
+    fs::read_to_string(hwmon_dir.join("name"))
+        .ok()
+        .map(|s| s.trim().to_string())
+}
+
+pub fn list_hwmon_dirs(root: &Path) -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = fs::read_dir(root)
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .map(|e| e.path())
+                .filter(|p| {
+                    p.file_name()
+                        .and_then(|n| n.to_str())
+                        .is_some_and(|n| n.starts_with("hwmon"))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    dirs.sort_by_key(|p| {
+        p.file_name()
+            .and_then(|n| n.to_str())
+            .and_then(|n| n.trim_start_matches("hwmon").parse::<u32>().ok())
+            .unwrap_or(u32::MAX)
+    });
+    dirs
+}
+
+fn find_by_name(root: &Path, name: &str) -> Result<PathBuf, String> {
+    let mut matches: Vec<PathBuf> = list_hwmon_dirs(root)
+        .into_iter()
+        .filter(|dir| read_name(dir).as_deref() == Some(name))
+        .collect();
+
+    match matches.len() {
+        0 => Err(format!("找不到名为 {} 的 hwmon 芯片", name)),
+        1 => Ok(matches.remove(0)),
+        _ => Err(format!(
+            "有多个名为 {} 的 hwmon 芯片 ({})，请改用 --hwmon-device 指定",
+            name,
+            matches.iter().map(|p| p.display().to_string()).collect::<Vec<_>>().join(", ")
+        )),
+    }
+}
+
+fn find_by_device(root: &Path, device: &str) -> Result<PathBuf, String> {
+    let device_path = fs::canonicalize(device)
+        .map_err(|e| format!("无法解析设备路径 {}: {}", device, e))?;
+
+    list_hwmon_dirs(root)
+        .into_iter()
+        .find(|dir| {
+            fs::canonicalize(dir).is_ok_and(|p| p.starts_with(&device_path))
+                || fs::canonicalize(dir.join("device")).is_ok_and(|p| p == device_path)
+        })
+        .ok_or_else(|| format!("设备 {} 下没有 hwmon 芯片", device))
+}
+
+impl PwmTarget {
+    pub fn resolve(&self, root: &Path) -> Result<String, String> {
+        let path = match self {
+            Self::Path(path) => PathBuf::from(path),
+            Self::Chip { name, channel } => find_by_name(root, name)?.join(format!("pwm{}", channel)),
+            Self::Device { device, channel } => {
+                find_by_device(root, device)?.join(format!("pwm{}", channel))
+            }
+        };
+
+        if !path.exists() {
+            return Err(format!("PWM 路径不存在: {} ({})", path.display(), self));
+        }
+        path.into_os_string()
+            .into_string()
+            .map_err(|p| format!("PWM 路径不是有效的 UTF-8: {:?}", p))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::TempDir;
+
+    #[test]
+    fn lists_chips_with_pwm_state() {
+        let root = TempDir::new("list");
+        for (dir, name) in [("hwmon10", "nct6775"), ("hwmon2", "k10temp")] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+            fs::write(root.join(dir).join("name"), format!("{}\n", name)).unwrap();
+        }
+        let chip = root.join("hwmon10");
+        for (file, value) in [
+            ("pwm1", "128\n"),
+            ("pwm1_enable", "1\n"),
+            ("fan1_input", "950\n"),
+            ("pwm10", "255\n"),
+            ("pwm2", "60\n"),
+            ("pwm2_enable", "5\n"),
+            ("pwm2_mode", "1\n"),
+        ] {
+            fs::write(chip.join(file), value).unwrap();
+        }
+
+        let chips = list_chips(&root);
+        let names: Vec<_> = chips.iter().map(|c| c.name.as_deref()).collect();
+        assert_eq!(names, [Some("k10temp"), Some("nct6775")]);
+        assert!(chips[0].channels.is_empty());
+
+        let channels = &chips[1].channels;
+        let numbers: Vec<u32> = channels.iter().map(|c| c.channel).collect();
+        assert_eq!(numbers, [1, 2, 10]);
+        assert_eq!(
+            channels[0].state,
+            PwmState {
+                pwm: Some(128),
+                enable: Some(1),
+                fan_input: Some(chip.join("fan1_input").to_string_lossy().into_owned()),
+                rpm: Some(950),
+                writable: true,
+            }
+        );
+        assert_eq!((channels[1].state.enable, channels[1].state.rpm), (Some(5), None));
+        assert_eq!((channels[2].state.pwm, channels[2].state.enable), (Some(255), None));
+    }
+
+    #[test]
+    fn resolves_pwm_by_chip_name() {
+        let root = TempDir::new("hwmon");
+        for (dir, name) in [("hwmon0", "acpitz"), ("hwmon3", "nct6775")] {
+            fs::create_dir_all(root.join(dir)).unwrap();
+            fs::write(root.join(dir).join("name"), format!("{}\n", name)).unwrap();
+        }
+        fs::write(root.join("hwmon3/pwm2"), "128\n").unwrap();
+
+        let target = PwmTarget::Chip {
+            name: "nct6775".to_string(),
+            channel: 2,
+        };
+        let expected = root.join("hwmon3/pwm2").to_string_lossy().into_owned();
+        assert_eq!(target.resolve(&root), Ok(expected));
+
+        let missing = PwmTarget::Chip {
+            name: "it87".to_string(),
+            channel: 1,
+        };
+        assert!(missing.resolve(&root).is_err());
+
+        // 同一文件经由符号链接和原路径出现两次
+        std::os::unix::fs::symlink(root.join("hwmon3"), root.join("hwmon9")).unwrap();
+        let direct = root.join("hwmon3/pwm2").to_string_lossy().into_owned();
+        let linked = root.join("hwmon9/pwm2").to_string_lossy().into_owned();
+        let other = root.join("hwmon0/pwm1").to_string_lossy().into_owned();
+        assert_eq!(find_duplicate_path([direct.as_str(), other.as_str()]), None);
+        assert_eq!(
+            find_duplicate_path([direct.as_str(), other.as_str(), linked.as_str()]),
+            Some((direct.as_str(), linked.as_str()))
+        );
+    }
+}
@@ -0,0 +1,260 @@
+use crate::{amdgpu, hwmon::SysfsValue, metric::Metric};
+use clap::ValueEnum;
+use nvml_wrapper::{
+    enum_wrappers::device::{Clock, TemperatureSensor, TemperatureThreshold},
+    Device, Nvml,
+};
+use serde::Serialize;
+use std::{collections::BTreeMap, fmt::Display, path::Path};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum InfoFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+#[derive(Serialize, Debug, Default, PartialEq, Eq)]
+pub struct Thresholds {
+    pub slowdown: Option<u32>,
+    pub shutdown: Option<u32>,
+}
+
+#[derive(Serialize, Debug, Default, PartialEq, Eq)]
+pub struct Utilization {
+    pub gpu: Option<u32>,
+    pub memory: Option<u32>,
+}
+
+#[derive(Serialize, Debug, Default, PartialEq, Eq)]
+pub struct Clocks {
+    pub graphics_mhz: Option<u32>,
+    pub sm_mhz: Option<u32>,
+    pub memory_mhz: Option<u32>,
+}
+
+#[derive(Serialize, Debug, Default, PartialEq)]
+pub struct NvidiaGpu {
+    pub index: u32,
+    pub name: Option<String>,
+    pub uuid: Option<String>,
+    pub pci_bus_id: Option<String>,
+    pub temperature: Option<u32>,
+    pub memory_temperature: Option<u32>,
+    pub thresholds: Thresholds,
+    // NVML 报告的是目标转速百分比，每个风扇一项
+    pub fan_speeds: Vec<Option<u32>>,
+    pub power_draw_w: Option<f64>,
+    pub power_limit_w: Option<f64>,
+    pub utilization: Utilization,
+    pub clocks: Clocks,
+}
+
+impl NvidiaGpu {
+    fn read(index: u32, device: &Device) -> Self {
+        let fans = device.num_fans().unwrap_or(0);
+        Self {
+            index,
+            name: device.name().ok(),
+            uuid: device.uuid().ok(),
+            pci_bus_id: device.pci_info().ok().map(|p| p.bus_id),
+            temperature: device.temperature(TemperatureSensor::Gpu).ok(),
+            memory_temperature: Metric::Memory.read(device),
+            thresholds: Thresholds {
+                slowdown: device
+                    .temperature_threshold(TemperatureThreshold::Slowdown)
+                    .ok(),
+                shutdown: device
+                    .temperature_threshold(TemperatureThreshold::Shutdown)
+                    .ok(),
+            },
+            fan_speeds: (0..fans).map(|i| device.fan_speed(i).ok()).collect(),
+            power_draw_w: device.power_usage().ok().map(|mw| mw as f64 / 1000.0),
+            power_limit_w: device
+                .enforced_power_limit()
+                .ok()
+                .map(|mw| mw as f64 / 1000.0),
+            utilization: device
+                .utilization_rates()
+                .map(|u| Utilization {
+                    gpu: Some(u.gpu),
+                    memory: Some(u.memory),
+                })
+                .unwrap_or_default(),
+            clocks: Clocks {
+                graphics_mhz: device.clock_info(Clock::Graphics).ok(),
+                sm_mhz: device.clock_info(Clock::SM).ok(),
+                memory_mhz: device.clock_info(Clock::Memory).ok(),
+            },
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Default, PartialEq)]
+pub struct AmdGpu {
+    pub index: usize,
+    pub hwmon: String,
+    pub pci_bus_id: Option<String>,
+    pub temperatures: BTreeMap<&'static str, Option<u32>>,
+    pub fan_rpm: Option<u32>,
+    pub power_draw_w: Option<f64>,
+}
+
+impl AmdGpu {
+    fn read(index: usize, chip: &amdgpu::AmdgpuChip) -> Self {
+        let read = |file: &str| {
+            SysfsValue::new(chip.dir.join(file).to_string_lossy().into_owned()).read::<u64>()
+        };
+        Self {
+            index,
+            hwmon: chip.dir.to_string_lossy().into_owned(),
+            pci_bus_id: chip.pci_bus_id.clone(),
+            temperatures: chip
+                .temperatures()
+                .into_iter()
+                .map(|(sensor, temp)| (sensor.label(), temp))
+                .collect(),
+            fan_rpm: read("fan1_input").and_then(|rpm| u32::try_from(rpm).ok()),
+            // 单位为微瓦，较新的内核只提供 power1_input
+            power_draw_w: read("power1_average")
+                .or_else(|| read("power1_input"))
+                .map(|uw| uw as f64 / 1_000_000.0),
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Default, PartialEq)]
+pub struct InfoReport {
+    pub nvidia: Vec<NvidiaGpu>,
+    pub amdgpu: Vec<AmdGpu>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nvml_error: Option<String>,
+}
+
+impl InfoReport {
+    pub fn collect(hwmon_root: &Path) -> Self {
+        let mut report = Self::default();
+        match Nvml::init() {
+            Ok(nvml) => {
+                let count = nvml.device_count().unwrap_or(0);
+                for i in 0..count {
+                    if let Ok(device) = nvml.device_by_index(i) {
+                        report.nvidia.push(NvidiaGpu::read(i, &device));
+                    }
+                }
+            }
+            Err(e) => report.nvml_error = Some(e.to_string()),
+        }
+        report.amdgpu = amdgpu::list_chips(hwmon_root)
+            .iter()
+            .enumerate()
+            .map(|(i, chip)| AmdGpu::read(i, chip))
+            .collect();
+        report
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nvidia.is_empty() && self.amdgpu.is_empty()
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("InfoReport 序列化不会失败")
+    }
+
+    pub fn print_text(&self) {
+        for gpu in &self.nvidia {
+            println!("GPU {}: {}", gpu.index, or_unknown(gpu.name.as_ref(), ""));
+            println!("  UUID: {}", or_unknown(gpu.uuid.as_ref(), ""));
+            println!("  PCI: {}", or_unknown(gpu.pci_bus_id.as_ref(), ""));
+            println!(
+                "  温度: {}，显存 {}，降频阈值 {}，关机阈值 {}",
+                or_unknown(gpu.temperature, "°C"),
+                or_unknown(gpu.memory_temperature, "°C"),
+                or_unknown(gpu.thresholds.slowdown, "°C"),
+                or_unknown(gpu.thresholds.shutdown, "°C")
+            );
+            if !gpu.fan_speeds.is_empty() {
+                let fans: Vec<String> =
+                    gpu.fan_speeds.iter().map(|s| or_unknown(*s, "%")).collect();
+                println!("  风扇: {}", fans.join(" "));
+            }
+            println!(
+                "  功耗: {} / {}",
+                or_unknown(gpu.power_draw_w.map(|w| format!("{:.1}", w)), " W"),
+                or_unknown(gpu.power_limit_w.map(|w| format!("{:.1}", w)), " W")
+            );
+            println!(
+                "  利用率: GPU {}，显存 {}",
+                or_unknown(gpu.utilization.gpu, "%"),
+                or_unknown(gpu.utilization.memory, "%")
+            );
+            println!(
+                "  频率: 图形 {}，SM {}，显存 {}",
+                or_unknown(gpu.clocks.graphics_mhz, " MHz"),
+                or_unknown(gpu.clocks.sm_mhz, " MHz"),
+                or_unknown(gpu.clocks.memory_mhz, " MHz")
+            );
+        }
+        for gpu in &self.amdgpu {
+            println!("AMD GPU {}: {}", gpu.index, gpu.hwmon);
+            println!("  PCI: {}", or_unknown(gpu.pci_bus_id.as_ref(), ""));
+            for (sensor, temp) in &gpu.temperatures {
+                println!("  温度 ({}): {}", sensor, or_unknown(*temp, "°C"));
+            }
+            if gpu.fan_rpm.is_some() {
+                println!("  风扇: {}", or_unknown(gpu.fan_rpm, " RPM"));
+            }
+            if let Some(w) = gpu.power_draw_w {
+                println!("  功耗: {:.1} W", w);
+            }
+        }
+    }
+}
+
+fn or_unknown<T: Display>(value: Option<T>, unit: &str) -> String {
+    value.map_or_else(|| "不可用".to_string(), |v| format!("{}{}", v, unit))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::TempDir;
+    use std::fs;
+
+    #[test]
+    fn serializes_amdgpu_report_as_json() {
+        let root = TempDir::new("info");
+        let dir = root.join("hwmon4");
+        fs::create_dir_all(&dir).unwrap();
+        for (file, value) in [
+            ("name", "amdgpu"),
+            ("temp1_label", "edge"),
+            ("temp1_input", "47000"),
+            ("temp2_label", "junction"),
+            ("temp2_input", "52000"),
+            ("fan1_input", "1400"),
+            ("power1_average", "35000000"),
+        ] {
+            fs::write(dir.join(file), format!("{}\n", value)).unwrap();
+        }
+
+        let report = InfoReport {
+            amdgpu: amdgpu::list_chips(&root)
+                .iter()
+                .enumerate()
+                .map(|(i, chip)| AmdGpu::read(i, chip))
+                .collect(),
+            ..InfoReport::default()
+        };
+        let json: serde_json::Value = serde_json::from_str(&report.to_json()).unwrap();
+        assert_eq!(json["nvidia"], serde_json::json!([]));
+        assert!(json.get("nvml_error").is_none());
+        let gpu = &json["amdgpu"][0];
+        assert_eq!(
+            gpu["temperatures"],
+            serde_json::json!({"edge": 47, "junction": 52})
+        );
+        assert_eq!(gpu["fan_rpm"], 1400);
+        assert_eq!(gpu["power_draw_w"], 35.0);
+    }
+}
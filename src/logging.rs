@@ -0,0 +1,270 @@
+use clap::ValueEnum;
+use log::{
+    kv::{self, VisitSource, VisitValue},
+    Level, LevelFilter, Log, Metadata, Record,
+};
+use serde_json::{Map, Value};
+use std::{
+    io::Write,
+    os::unix::net::UnixDatagram,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+const JOURNALD_SOCKET: &str = "/run/systemd/journal/socket";
+const SYSLOG_IDENTIFIER: &str = "gpu-fan-controller";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum LogFormat {
+    #[default]
+    Text,
+    Json,
+    Journald,
+}
+
+// 尽量保留数值类型，JSON 输出中温度和 PWM 不会变成字符串
+struct JsonValue(Value);
+
+impl<'v> VisitValue<'v> for &mut JsonValue {
+    fn visit_any(&mut self, value: kv::Value) -> Result<(), kv::Error> {
+        self.0 = Value::from(value.to_string());
+        Ok(())
+    }
+
+    fn visit_null(&mut self) -> Result<(), kv::Error> {
+        self.0 = Value::Null;
+        Ok(())
+    }
+
+    fn visit_u64(&mut self, value: u64) -> Result<(), kv::Error> {
+        self.0 = Value::from(value);
+        Ok(())
+    }
+
+    fn visit_i64(&mut self, value: i64) -> Result<(), kv::Error> {
+        self.0 = Value::from(value);
+        Ok(())
+    }
+
+    fn visit_f64(&mut self, value: f64) -> Result<(), kv::Error> {
+        self.0 = Value::from(value);
+        Ok(())
+    }
+
+    fn visit_bool(&mut self, value: bool) -> Result<(), kv::Error> {
+        self.0 = Value::from(value);
+        Ok(())
+    }
+
+    fn visit_str(&mut self, value: &str) -> Result<(), kv::Error> {
+        self.0 = Value::from(value);
+        Ok(())
+    }
+}
+
+struct Fields(Vec<(String, Value)>);
+
+impl<'kvs> VisitSource<'kvs> for Fields {
+    fn visit_pair(&mut self, key: kv::Key<'kvs>, value: kv::Value<'kvs>) -> Result<(), kv::Error> {
+        let mut json = JsonValue(Value::Null);
+        value.visit(&mut json)?;
+        // 值为 None 的字段直接省略
+        if !json.0.is_null() {
+            self.0.push((key.as_str().to_string(), json.0));
+        }
+        Ok(())
+    }
+}
+
+fn fields(record: &Record) -> Vec<(String, Value)> {
+    let mut fields = Fields(Vec::new());
+    let _ = record.key_values().visit(&mut fields);
+    fields.0
+}
+
+fn field_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        v => v.to_string(),
+    }
+}
+
+// RFC 3339 UTC 时间戳，精确到毫秒
+fn format_timestamp(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = since_epoch.as_secs();
+    let (days, rem) = (secs / 86_400, secs % 86_400);
+
+    // Howard Hinnant 的 civil_from_days 算法
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60,
+        since_epoch.subsec_millis()
+    )
+}
+
+fn format_text(record: &Record, timestamp: &str) -> String {
+    let mut line = format!("{} {:<5} {}", timestamp, record.level(), record.args());
+    for (key, value) in fields(record) {
+        line.push_str(&format!(" {}={}", key, field_text(&value)));
+    }
+    line
+}
+
+fn format_json(record: &Record, timestamp: &str) -> String {
+    let mut object = Map::new();
+    object.insert("timestamp".to_string(), Value::from(timestamp));
+    object.insert("level".to_string(), Value::from(record.level().as_str().to_ascii_lowercase()));
+    object.insert("target".to_string(), Value::from(record.target()));
+    object.insert("message".to_string(), Value::from(record.args().to_string()));
+    for (key, value) in fields(record) {
+        object.insert(key, value);
+    }
+    Value::Object(object).to_string()
+}
+
+fn syslog_priority(level: Level) -> u8 {
+    match level {
+        Level::Error => 3,
+        Level::Warn => 4,
+        Level::Info => 6,
+        Level::Debug | Level::Trace => 7,
+    }
+}
+
+// journald 原生协议：每行 KEY=value，值中含换行时改用 KEY\n<u64 长度><值>\n
+fn push_journal_field(buf: &mut Vec<u8>, key: &str, value: &str) {
+    buf.extend_from_slice(key.as_bytes());
+    if value.contains('\n') {
+        buf.push(b'\n');
+        buf.extend_from_slice(&(value.len() as u64).to_le_bytes());
+    } else {
+        buf.push(b'=');
+    }
+    buf.extend_from_slice(value.as_bytes());
+    buf.push(b'\n');
+}
+
+fn format_journal(record: &Record) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(256);
+    push_journal_field(&mut buf, "MESSAGE", &record.args().to_string());
+    push_journal_field(&mut buf, "PRIORITY", &syslog_priority(record.level()).to_string());
+    push_journal_field(&mut buf, "SYSLOG_IDENTIFIER", SYSLOG_IDENTIFIER);
+    push_journal_field(&mut buf, "CODE_MODULE", record.target());
+    for (key, value) in fields(record) {
+        // 字段名只能由大写字母、数字和下划线组成
+        let key: String = key
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_uppercase() } else { '_' })
+            .collect();
+        push_journal_field(&mut buf, key.trim_start_matches('_'), &field_text(&value));
+    }
+    buf
+}
+
+struct Logger {
+    format: LogFormat,
+    journal: Option<UnixDatagram>,
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        if let Some(ref journal) = self.journal {
+            if journal.send_to(&format_journal(record), JOURNALD_SOCKET).is_ok() {
+                return;
+            }
+        }
+        let timestamp = format_timestamp(SystemTime::now());
+        let line = match self.format {
+            LogFormat::Json => format_json(record, &timestamp),
+            LogFormat::Text | LogFormat::Journald => format_text(record, &timestamp),
+        };
+        let _ = writeln!(std::io::stderr().lock(), "{}", line);
+    }
+
+    fn flush(&self) {
+        let _ = std::io::stderr().flush();
+    }
+}
+
+// journald 不可用时退回到 stderr 文本输出
+pub fn init(level: LevelFilter, format: LogFormat) {
+    let journal = match format {
+        LogFormat::Journald => UnixDatagram::unbound().ok(),
+        _ => None,
+    };
+    if log::set_boxed_logger(Box::new(Logger { format, journal })).is_ok() {
+        log::set_max_level(level);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn with_record<T>(level: Level, f: impl FnOnce(&Record) -> T) -> T {
+        let fields: [(&str, kv::Value); 4] = [
+            ("pwm_path", kv::Value::from("/sys/class/hwmon/hwmon2/pwm1")),
+            ("gpu", kv::Value::null()),
+            ("temperature", kv::Value::from(61u32)),
+            ("pwm", kv::Value::from(180u8)),
+        ];
+        let record = Record::builder()
+            .level(level)
+            .target("gpu_fan_controller::fan")
+            .args(format_args!("风扇速度已更新"))
+            .key_values(&fields)
+            .build();
+        f(&record)
+    }
+
+    #[test]
+    fn formats_timestamps_as_utc() {
+        let time = UNIX_EPOCH + Duration::from_millis(1_709_251_199_123);
+        assert_eq!(format_timestamp(time), "2024-02-29T23:59:59.123Z");
+        assert_eq!(format_timestamp(UNIX_EPOCH), "1970-01-01T00:00:00.000Z");
+    }
+
+    #[test]
+    fn renders_fields_in_each_format() {
+        let text = with_record(Level::Debug, |r| format_text(r, "T"));
+        assert_eq!(text, "T DEBUG 风扇速度已更新 pwm_path=/sys/class/hwmon/hwmon2/pwm1 temperature=61 pwm=180");
+
+        let json: Value = serde_json::from_str(&with_record(Level::Info, |r| format_json(r, "T"))).unwrap();
+        assert_eq!(json["level"], "info");
+        assert_eq!(json["temperature"], 61);
+        assert_eq!(json["pwm_path"], "/sys/class/hwmon/hwmon2/pwm1");
+
+        let journal = with_record(Level::Warn, format_journal);
+        let journal = String::from_utf8(journal).unwrap();
+        assert!(journal.contains("PRIORITY=4\n"));
+        assert!(journal.contains("TEMPERATURE=61\n"));
+        assert!(journal.contains("PWM_PATH=/sys/class/hwmon/hwmon2/pwm1\n"));
+
+        let mut buf = Vec::new();
+        push_journal_field(&mut buf, "MESSAGE", "a\nb");
+        assert_eq!(buf, b"MESSAGE\n\x03\0\0\0\0\0\0\0a\nb\n");
+    }
+}
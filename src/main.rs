@@ -1,8 +1,48 @@
+mod amdgpu;
+mod calibrate;
+mod cli;
+mod config;
+mod curve;
+mod error;
+mod failsafe;
+mod fan;
+mod filter;
+mod gpu;
+mod hwmon;
+mod info;
+mod logging;
+mod metric;
+mod pid;
+mod pwm;
+mod resume;
+mod sensor;
+mod slew;
+mod stall;
+#[cfg(test)]
+mod testutil;
+
+use amdgpu::AmdgpuSource;
+use calibrate::{Calibrator, FanProfile};
 use clap::Parser;
-use nvml_wrapper::{Nvml, enum_wrappers::device::TemperatureSensor};
+use cli::{Args, CalibrateArgs, Command, InfoArgs, ListPwmArgs, RunArgs, SetArgs, StatusArgs};
+use config::Config;
+use curve::Hysteresis;
+use error::FanError;
+use failsafe::FailsafeSettings;
+use fan::{ChannelOptions, Control, ControlMode, FanChannel};
+use gpu::{Aggregation, GpuSelector, GpuSet};
+use hwmon::{PwmState, PwmTarget, SysfsValue};
+use info::{InfoFormat, InfoReport};
+use log::{error, info, warn};
+use metric::MetricExpr;
+use pid::{PidController, PidSettings};
+use pwm::PwmOutput;
+use resume::ResumeDetector;
+use nvml_wrapper::Nvml;
+use sensor::{HwmonSource, NvmlSource, TemperatureSource};
+use slew::SlewLimiter;
+use stall::StallSettings;
 use std::{
-    fs::{File, OpenOptions},
-    io::{Read, Write, Seek, SeekFrom},
     path::Path,
     process::exit,
     sync::{
@@ -13,250 +53,417 @@ use std::{
     time::Duration,
 };
 
-#[derive(Parser, Debug)]
-struct Args {
-    pwm_path: Option<String>,
-    #[arg(long, default_value_t = 2.0)]
-    interval: f64,
-    #[arg(long)]
-    info: bool,
-}
+const EXIT_FAN_STALL: i32 = 3;
 
-struct FileBuffer {
-    path_buf: String,
-    content_buf: String,
+struct FanController {
+    channels: Vec<FanChannel>,
 }
 
-impl FileBuffer {
-    fn new() -> Self {
-        Self {
-            path_buf: String::with_capacity(64),
-            content_buf: String::with_capacity(16),
+impl FanController {
+    fn update(&mut self) -> bool {
+        let mut stalled = false;
+        for channel in &mut self.channels {
+            stalled |= channel.update();
         }
+        stalled
     }
 
-    fn make_enable_path(&mut self, pwm_path: &str) {
-        self.path_buf.clear();
-        self.path_buf.push_str(pwm_path);
-        self.path_buf.push_str("_enable");
+    fn reinitialize(&mut self) {
+        for channel in &mut self.channels {
+            channel.reinitialize();
+        }
     }
 }
 
-struct CachedFiles {
-    pwm_file: Option<File>,
-    enable_file: Option<File>,
+static RUNNING: AtomicBool = AtomicBool::new(true);
+
+fn setup_signal_handler() {
+    ctrlc::set_handler(|| {
+        info!(event = "shutdown_requested"; "接收到退出信号，正在准备关闭...");
+        RUNNING.store(false, Ordering::Relaxed);
+    })
+    .unwrap_or_else(|_| {
+        error!(event = "signal_handler_failed"; "无法设置信号处理器");
+    });
 }
 
-impl CachedFiles {
-    fn new() -> Self {
-        Self {
-            pwm_file: None,
-            enable_file: None,
-        }
+fn resolve_pwm_target(target: &PwmTarget, hwmon_root: &Path) -> String {
+    let path = target.resolve(hwmon_root).unwrap_or_else(|e| {
+        error!(event = "resolve_failed"; "{}", e);
+        exit(1);
+    });
+    if !matches!(target, PwmTarget::Path(_)) {
+        info!(event = "pwm_resolved", pwm_path = path; "{} -> {}", target, path);
     }
+    path
+}
 
-    fn get_or_open_pwm(&mut self, path: &str) -> Option<&mut File> {
-        if self.pwm_file.is_none() {
-            self.pwm_file = OpenOptions::new().write(true).open(path).ok();
-        }
-        self.pwm_file.as_mut()
+fn load_profile(path: &str, pwm_path: &str) -> FanProfile {
+    let profile = FanProfile::load(path).unwrap_or_else(|e| {
+        error!(event = "profile_load_failed"; "{}", e);
+        exit(1);
+    });
+    if profile.pwm_path != pwm_path {
+        warn!(
+            event = "profile_mismatch", pwm_path;
+            "风扇配置档 {} 校准于 {}，当前通道为 {}", path, profile.pwm_path, pwm_path
+        );
     }
+    info!(
+        event = "profile_loaded", pwm_path;
+        "已加载风扇配置档: 启动 PWM {}，停转 PWM {}，最高 {} RPM", profile.start_pwm, profile.stop_pwm, profile.max_rpm
+    );
+    profile
+}
 
-    fn get_or_open_enable(&mut self, path: &str) -> Option<&mut File> {
-        if self.enable_file.is_none() {
-            self.enable_file = OpenOptions::new()
-                .read(true)
-                .write(true)
-                .open(path)
-                .ok();
-        }
-        self.enable_file.as_mut()
+fn run_calibrate(args: CalibrateArgs) {
+    let Some(target) = args.target.target() else {
+        error!(event = "invalid_argument"; "必须指定 PWM 路径");
+        exit(1);
+    };
+    let pwm_path = resolve_pwm_target(&target, Path::new(&args.target.root.hwmon_root));
+    let fan_input = args
+        .fan_input
+        .or_else(|| hwmon::fan_input_for(&pwm_path))
+        .unwrap_or_else(|| {
+            error!(event = "fan_input_missing", pwm_path; "找不到对应的 fanN_input，请用 --fan-input 指定");
+            exit(1);
+        });
+    if !args.settle.is_finite() || args.settle <= 0.0 {
+        error!(event = "invalid_argument"; "--settle 必须为正数: {}", args.settle);
+        exit(1);
     }
-}
 
-struct FanController {
-    nvml: Nvml,
-    pwm_path: String,
-    enable_path: String,
-    last_temp: u32,
-    last_speed: u8,
-    buffer: FileBuffer,
-    files: CachedFiles,
+    let mut tach = SysfsValue::new(fan_input);
+    let mut output = PwmOutput::new(pwm_path.clone(), None).unwrap_or_else(|e| {
+        error!(event = "channel_init_failed", pwm_path; "无法初始化风扇控制器: {}", e);
+        exit(1);
+    });
+    setup_signal_handler();
+
+    println!(
+        "开始校准 {} (转速: {})，期间风扇转速会大幅变化。按 Ctrl+C 中止。",
+        pwm_path,
+        tach.path()
+    );
+    let result = Calibrator::new(&mut output, |_| tach.read(), Duration::from_secs_f64(args.settle), &RUNNING)
+        .run(args.step);
+    drop(output);
+
+    let profile = result.unwrap_or_else(|e| {
+        error!(event = "calibration_failed", pwm_path, fan_input = tach.path(); "校准失败: {} (转速: {})", e, tach.path());
+        exit(1);
+    });
+    if let Err(e) = profile.save(&args.output) {
+        error!(event = "profile_save_failed"; "{}", e);
+        exit(1);
+    }
+    println!(
+        "校准完成: 启动 PWM {}，停转 PWM {}，最高 {} RPM，已写入 {}",
+        profile.start_pwm, profile.stop_pwm, profile.max_rpm, args.output
+    );
 }
 
-impl FanController {
-    fn new(nvml: Nvml, pwm_path: String) -> Option<Self> {
-        let mut buffer = FileBuffer::new();
-        buffer.make_enable_path(&pwm_path);
-        
-        if !Path::new(&buffer.path_buf).exists() {
-            return None;
+fn run_list_pwm(args: ListPwmArgs) {
+    let root = Path::new(&args.root.hwmon_root);
+    let chips = hwmon::list_chips(root);
+    if chips.is_empty() {
+        error!(event = "no_hwmon"; "{} 下没有找到 hwmon 芯片", root.display());
+        exit(1);
+    }
+    for chip in chips {
+        println!("{} ({})", chip.dir.display(), chip.name.as_deref().unwrap_or("?"));
+        if chip.channels.is_empty() {
+            println!("  无 PWM 通道");
         }
-
-        let enable_path = buffer.path_buf.clone();
-        let mut controller = Self {
-            nvml,
-            pwm_path,
-            enable_path,
-            last_temp: 0,
-            last_speed: 0,
-            buffer,
-            files: CachedFiles::new(),
-        };
-
-        if !controller.set_pwm_mode(1) {
-            return None;
+        for channel in chip.channels {
+            println!("  pwm{:<3} {}", channel.channel, channel.state);
         }
-
-        Some(controller)
     }
+}
 
-    #[inline(always)]
-    fn calculate_fan_speed(temp: u32) -> u8 {
-        match temp {
-            0..=25 => 77,
-            26..=59 => 77 + ((temp - 25) * 5).min(178) as u8,
-            _ => 255,
-        }
+fn run_set(args: SetArgs) {
+    let Some(target) = args.target.target() else {
+        error!(event = "invalid_argument"; "必须指定 PWM 路径");
+        exit(1);
+    };
+    let pwm_path = resolve_pwm_target(&target, Path::new(&args.target.root.hwmon_root));
+    let mut output = PwmOutput::new(pwm_path.clone(), args.exit_mode).unwrap_or_else(|e| {
+        error!(event = "channel_init_failed", pwm_path; "无法初始化风扇控制器: {}", e);
+        exit(1);
+    });
+    if let Err(e) = output.set_fan_speed(args.value) {
+        error!(event = "pwm_write_failed", pwm_path, pwm = args.value; "{}", e);
+        drop(output);
+        exit(1);
     }
+    setup_signal_handler();
 
-    #[inline(always)]
-    fn get_gpu_temp(&self) -> Option<u32> {
-        self.nvml
-            .device_by_index(0)
-            .ok()?
-            .temperature(TemperatureSensor::Gpu)
-            .ok()
-            .map(|t| t as u32)
+    info!(event = "pwm_set", pwm_path, pwm = args.value; "风扇速度已固定为 {} / 255。按 Ctrl+C 恢复自动模式。", args.value);
+    while RUNNING.load(Ordering::Relaxed) {
+        sleep(Duration::from_millis(200));
     }
+}
 
-    fn read_u8_from_enable_file(&mut self) -> Option<u8> {
-        let enable_path = self.enable_path.clone();
-        let file = self.files.get_or_open_enable(&enable_path)?;
-        self.buffer.content_buf.clear();
-        file.seek(SeekFrom::Start(0)).ok()?;
-        file.read_to_string(&mut self.buffer.content_buf).ok()?;
-        self.buffer.content_buf.trim().parse().ok()
+fn run_status(args: StatusArgs) {
+    let root = Path::new(&args.target.root.hwmon_root);
+    let mut targets: Vec<PwmTarget> = args.target.target().into_iter().collect();
+    if let Some(path) = args.config.as_deref() {
+        let config = Config::load(path).unwrap_or_else(|e| {
+            error!(event = "invalid_config"; "{}", e);
+            exit(1);
+        });
+        targets.extend(config.fans.into_iter().map(|fan| fan.target));
+    }
+    if targets.is_empty() {
+        error!(event = "invalid_argument"; "必须指定 PWM 路径或 --config");
+        exit(1);
     }
 
-    fn write_u8_to_pwm_file(&mut self, val: u8) -> bool {
-        let pwm_path = self.pwm_path.clone();
-        if let Some(file) = self.files.get_or_open_pwm(&pwm_path) {
-            file.seek(SeekFrom::Start(0)).is_ok()
-                && file.write_all(val.to_string().as_bytes()).is_ok()
-                && file.flush().is_ok()
-        } else {
-            false
+    for target in targets {
+        match target.resolve(root) {
+            Ok(path) => println!("[{}] {}", path, PwmState::read(&path)),
+            Err(e) => error!(event = "resolve_failed"; "{}", e),
         }
     }
+}
 
-    fn write_u8_to_enable_file(&mut self, val: u8) -> bool {
-        let enable_path = self.enable_path.clone();
-        if let Some(file) = self.files.get_or_open_enable(&enable_path) {
-            file.seek(SeekFrom::Start(0)).is_ok()
-                && file.write_all(val.to_string().as_bytes()).is_ok()
-                && file.flush().is_ok()
-        } else {
-            false
-        }
+fn run_daemon(args: RunArgs) {
+    if !args.interval.is_finite() || args.interval <= 0.0 {
+        error!(event = "invalid_argument"; "--interval 必须为正数: {}", args.interval);
+        exit(1);
     }
+    let config = match args.config.as_deref() {
+        Some(path) => Config::load(path).unwrap_or_else(|e| {
+            error!(event = "invalid_config"; "{}", e);
+            exit(1);
+        }),
+        None => Config::default(),
+    };
 
-    fn set_pwm_mode(&mut self, mode: u8) -> bool {
-        if let Some(current) = self.read_u8_from_enable_file() {
-            if current != mode {
-                return self.write_u8_to_enable_file(mode);
-            }
-            return true;
+    let hwmon_root = Path::new(&args.target.root.hwmon_root);
+
+    let curve = config.curve.unwrap_or_default();
+    let hysteresis = args.hysteresis.or(config.hysteresis);
+    let metric = args.metric.clone().or(config.metric).unwrap_or_default();
+    let mode = args.mode.or(config.mode).unwrap_or_default();
+    let pid = PidSettings::default()
+        .merge(&config.pid)
+        .merge(&args.pid)
+        .validate()
+        .unwrap_or_else(|e| {
+            error!(event = "invalid_config"; "{}", e);
+            exit(1);
+        });
+    let filter = args.filter.or(config.filter);
+    let stall = match args.stall_timeout.or(config.stall_timeout) {
+        Some(timeout) if timeout.is_finite() && timeout > 0.0 => Some(StallSettings {
+            pwm_threshold: args.stall_pwm.or(config.stall_pwm).unwrap_or(100),
+            timeout: Duration::from_secs_f64(timeout),
+            hook: args.on_stall.clone().or(config.on_stall),
+            exit: args.exit_on_stall.or(config.exit_on_stall).unwrap_or(false),
+        }),
+        Some(timeout) => {
+            error!(event = "invalid_config"; "停转检测超时必须为正数: {}", timeout);
+            exit(1);
+        }
+        None => None,
+    };
+    let exit_mode = args.exit_mode.or(config.exit_mode);
+    let failsafe_timeout = match args.failsafe_timeout.or(config.failsafe_timeout) {
+        Some(timeout) if timeout.is_finite() && timeout > 0.0 => {
+            Some(Duration::from_secs_f64(timeout))
+        }
+        Some(timeout) => {
+            error!(event = "invalid_config"; "失效保护超时必须为正数: {}", timeout);
+            exit(1);
+        }
+        None => None,
+    };
+    let verify = args
+        .verify_writes
+        .or(config.verify_writes)
+        .unwrap_or(false)
+        .then(|| args.verify_tolerance.or(config.verify_tolerance).unwrap_or(0));
+    // 0 表示不检查
+    let mode_check = match args.mode_check_interval.or(config.mode_check_interval).unwrap_or(10.0) {
+        0.0 => None,
+        interval if interval.is_finite() && interval > 0.0 => {
+            Some(Duration::from_secs_f64(interval))
         }
-        false
+        interval => {
+            error!(event = "invalid_config"; "模式检查间隔不能为负数: {}", interval);
+            exit(1);
+        }
+    };
+    let defaults = FailsafeSettings::default();
+    let failsafe = FailsafeSettings {
+        errors: args.failsafe_errors.or(config.failsafe_errors).unwrap_or(defaults.errors),
+        timeout: failsafe_timeout,
+        pwm: args.failsafe_pwm.or(config.failsafe_pwm).unwrap_or(defaults.pwm),
     }
-
-    fn set_fan_speed(&mut self, speed: u8) -> bool {
-        self.write_u8_to_pwm_file(speed)
+    .validate()
+    .unwrap_or_else(|e| {
+        error!(event = "invalid_config"; "{}", e);
+        exit(1);
+    });
+    let ramp_up = args.ramp_up.or(config.ramp_up);
+    let ramp_down = args.ramp_down.or(config.ramp_down);
+    if let Err(e) = SlewLimiter::validate_rate("--ramp-up", args.ramp_up)
+        .and(SlewLimiter::validate_rate("--ramp-down", args.ramp_down))
+    {
+        error!(event = "invalid_config"; "{}", e);
+        exit(1);
     }
+    let fans = config.fans;
 
-    fn update(&mut self) {
-        if let Some(temp) = self.get_gpu_temp() {
-            let speed = Self::calculate_fan_speed(temp);
-            if temp != self.last_temp || speed != self.last_speed {
-                if self.set_fan_speed(speed) {
-                    println!("温度: {}°C，风扇速度: {} / 255", temp, speed);
-                    self.last_temp = temp;
-                    self.last_speed = speed;
-                }
-            }
-        } else if self.last_speed != 77 {
-            if self.set_fan_speed(77) {
-                println!("无法读取温度，使用默认速度 77");
-                self.last_speed = 77;
-            }
-        }
-    }
+    let make_control = |curve, hysteresis: Option<u32>| match mode {
+        ControlMode::Curve => Control::Curve(curve, Hysteresis::new(hysteresis.unwrap_or(0))),
+        ControlMode::Pid => Control::Pid(PidController::new(pid.clone())),
+    };
 
-    fn cleanup(&mut self) {
-        println!("正在执行清理...");
-        let _ = self.set_fan_speed(77);
-        let _ = self.set_pwm_mode(2);
-    }
-}
+    let cli_target = args.target.target();
 
-// **关键修复**：为 FanController 实现 Drop 特性
-impl Drop for FanController {
-    fn drop(&mut self) {
-        self.cleanup();
+    if cli_target.is_none() && fans.is_empty() {
+        error!(event = "invalid_argument"; "必须指定 PWM 路径");
+        exit(1);
     }
-}
 
-static RUNNING: AtomicBool = AtomicBool::new(true);
+    let resolve_target = |target: &PwmTarget| resolve_pwm_target(target, hwmon_root);
+    let cli_pwm_path = cli_target.as_ref().map(resolve_target);
+    let fan_pwm_paths: Vec<String> = fans.iter().map(|f| resolve_target(&f.target)).collect();
+    // 两个通道控制同一个文件时，后创建的会把前者设置的手动模式当作原始状态，退出时无法恢复
+    if let Some((first, second)) =
+        hwmon::find_duplicate_path(cli_pwm_path.iter().chain(&fan_pwm_paths).map(String::as_str))
+    {
+        error!(event = "duplicate_pwm", pwm_path = second; "PWM 通道被重复指定: {} 与 {} 为同一文件", first, second);
+        exit(1);
+    }
 
-fn setup_signal_handler() {
-    ctrlc::set_handler(|| {
-        println!("\n接收到退出信号，正在准备关闭...");
-        RUNNING.store(false, Ordering::Relaxed);
-    })
-    .unwrap_or_else(|_| {
-        eprintln!("无法设置信号处理器");
-    });
-}
+    let selectors: Vec<GpuSelector> = args
+        .gpu
+        .iter()
+        .cloned()
+        .chain(args.gpu_uuid.iter().map(|u| GpuSelector::Uuid(u.clone())))
+        .chain(args.gpu_pci.iter().map(|p| GpuSelector::PciBusId(p.clone())))
+        .collect();
+
+    let mut nvml: Option<Option<Arc<Nvml>>> = None;
+    let mut make_source = |pwm_path: &str,
+                           gpu: Option<&GpuSelector>,
+                           temp_input: Option<&String>,
+                           metric: &MetricExpr|
+     -> Box<dyn TemperatureSource> {
+        if let Some(path) = temp_input.or(args.temp_input.as_ref().filter(|_| gpu.is_none())) {
+            info!(event = "temp_source", pwm_path; "温度来源: {}", path);
+            return Box::new(HwmonSource::new(path.clone()).unwrap_or_else(|e| {
+                error!(event = "temp_source_failed", pwm_path; "{}", e);
+                exit(1);
+            }));
+        }
 
-fn main() {
-    let args = Args::parse();
+        let nvml = nvml
+            .get_or_insert_with(|| match Nvml::init() {
+                Ok(nvml) => Some(Arc::new(nvml)),
+                Err(e) => {
+                    warn!(event = "nvml_unavailable"; "{}，改用 amdgpu", FanError::NvmlInit(e));
+                    None
+                }
+            })
+            .clone();
 
-    let nvml = Nvml::init().unwrap_or_else(|e| {
-        eprintln!("无法初始化 NVML: {}", e);
-        exit(1);
-    });
+        let (selectors, aggregation) = match gpu {
+            Some(selector) => (std::slice::from_ref(selector), &Aggregation::Max),
+            None => (selectors.as_slice(), &args.aggregate),
+        };
 
-    if args.info {
-        if let Ok(count) = nvml.device_count() {
-            for i in 0..count {
-                if let Ok(device) = nvml.device_by_index(i) {
-                    match device.temperature(TemperatureSensor::Gpu) {
-                        Ok(temp) => println!("GPU {} 温度: {}°C", i, temp),
-                        Err(e) => println!("GPU {} 温度读取失败: {}", i, e),
-                    }
-                }
+        let Some(nvml) = nvml else {
+            let source = AmdgpuSource::discover(hwmon_root, selectors, aggregation, args.amd_sensor)
+                .unwrap_or_else(|e| {
+                    error!(event = "temp_source_failed", pwm_path; "{}", e);
+                    exit(1);
+                });
+            info!(event = "temp_source", pwm_path; "温度来源: {:?}", source.paths());
+            if *metric != MetricExpr::default() {
+                warn!(event = "metric_unsupported"; "amdgpu 不支持指标 {}，改用 --amd-sensor 选择传感器", metric);
             }
-        }
-        return;
-    }
+            return Box::new(source);
+        };
 
-    let pwm_path = match args.pwm_path {
-        Some(ref p) if Path::new(p).exists() => p.clone(),
-        Some(ref p) => {
-            eprintln!("PWM 路径不存在: {}", p);
+        let gpus = GpuSet::resolve(&nvml, selectors, aggregation, metric).unwrap_or_else(|e| {
+            error!(event = "temp_source_failed", pwm_path; "{}", e);
             exit(1);
-        }
-        None => {
-            eprintln!("必须指定 PWM 路径");
-            exit(1);
-        }
+        });
+        info!(event = "temp_source", pwm_path; "监控 GPU: {:?}，指标: {}", gpus.indices(), gpus.metric());
+        Box::new(NvmlSource::new(nvml, gpus))
     };
 
-    let controller = FanController::new(nvml, pwm_path).unwrap_or_else(|| {
-        eprintln!("无法初始化风扇控制器");
-        exit(1);
-    });
+    let mut channels = Vec::with_capacity(fans.len() + 1);
+    if let Some(pwm_path) = cli_pwm_path {
+        let source = make_source(&pwm_path, None, None, &metric);
+        let min_pwm = args
+            .profile
+            .as_deref()
+            .map_or(0, |path| load_profile(path, &pwm_path).start_pwm);
+        let options = ChannelOptions {
+            min_pwm,
+            filter,
+            ramp_up,
+            ramp_down,
+            fan_input: args.fan_input.clone().or_else(|| hwmon::fan_input_for(&pwm_path)),
+            stall: stall.clone(),
+            exit_mode,
+            failsafe: failsafe.clone(),
+            mode_check,
+            target: cli_target.clone(),
+            hwmon_root: hwmon_root.to_path_buf(),
+            verify,
+        };
+        channels.push((source, pwm_path, make_control(curve.clone(), hysteresis), options));
+    }
+    for (fan, pwm_path) in fans.into_iter().zip(fan_pwm_paths) {
+        let source = make_source(
+            &pwm_path,
+            fan.gpu.as_ref(),
+            fan.temp_input.as_ref(),
+            fan.metric.as_ref().unwrap_or(&metric),
+        );
+        let control = make_control(fan.curve.unwrap_or_else(|| curve.clone()), fan.hysteresis.or(hysteresis));
+        let profile_pwm = fan
+            .profile
+            .as_deref()
+            .map_or(0, |path| load_profile(path, &pwm_path).start_pwm);
+        let options = ChannelOptions {
+            min_pwm: fan.min_pwm.max(profile_pwm),
+            filter: fan.filter.or(filter),
+            ramp_up: fan.ramp_up.or(ramp_up),
+            ramp_down: fan.ramp_down.or(ramp_down),
+            fan_input: fan.fan_input.or_else(|| hwmon::fan_input_for(&pwm_path)),
+            stall: stall.clone(),
+            exit_mode: fan.exit_mode.or(exit_mode),
+            failsafe: failsafe.clone(),
+            mode_check,
+            target: Some(fan.target),
+            hwmon_root: hwmon_root.to_path_buf(),
+            verify,
+        };
+        channels.push((source, pwm_path, control, options));
+    }
+
+    let mut controller = FanController {
+        channels: Vec::with_capacity(channels.len()),
+    };
+    for (source, pwm_path, control, options) in channels {
+        match FanChannel::new(source, pwm_path.clone(), control, options) {
+            Ok(channel) => controller.channels.push(channel),
+            Err(e) => {
+                error!(event = "channel_init_failed", pwm_path; "无法初始化风扇控制器: {}", e);
+                // 先恢复已接管的风扇，exit 不会执行 drop
+                drop(controller);
+                exit(1);
+            }
+        }
+    }
 
     let controller_arc = Arc::new(Mutex::new(controller));
     setup_signal_handler();
@@ -264,19 +471,69 @@ fn main() {
     let sleep_nanos = (args.interval * 1_000_000_000.0) as u64;
     let sleep_duration = Duration::from_nanos(sleep_nanos);
 
-    println!("风扇控制器已启动，监控间隔: {:.2}秒。按 Ctrl+C 退出。", args.interval);
+    if mode == ControlMode::Pid {
+        info!(
+            event = "pid_settings"; "PID 模式: 目标 {}°C，Kp={} Ki={} Kd={}，PWM {}-{}",
+            pid.target, pid.kp, pid.ki, pid.kd, pid.min_pwm, pid.max_pwm
+        );
+    }
+    info!(event = "started", interval = args.interval; "风扇控制器已启动，监控间隔: {:.2}秒。按 Ctrl+C 退出。", args.interval);
 
+    let mut resume = ResumeDetector::new(sleep_duration);
+    let mut exit_code = 0;
     while RUNNING.load(Ordering::Relaxed) {
         {
             if let Ok(mut ctrl) = controller_arc.lock() {
-                ctrl.update();
+                if let Some(gap) = resume.poll() {
+                    warn!(
+                        event = "resume_detected"; "检测到 {:.1} 秒的时间跳变，可能刚从挂起中恢复，重新初始化风扇通道",
+                        gap.as_secs_f64()
+                    );
+                    ctrl.reinitialize();
+                }
+                if ctrl.update() {
+                    error!(event = "stall_exit"; "检测到风扇停转，按 --exit-on-stall 要求退出");
+                    exit_code = EXIT_FAN_STALL;
+                    break;
+                }
             }
         }
         sleep(sleep_duration);
     }
 
-    println!("程序即将退出。");
+    info!(event = "stopping"; "程序即将退出。");
+    if exit_code != 0 {
+        drop(controller_arc);
+        exit(exit_code);
+    }
     // **关键修复**：不再需要手动调用 cleanup。
     // 当 main 函数结束时，controller_arc 会被销毁，
-    // 其内部的 FanController 的 drop 方法会自动被调用。
-}
\ No newline at end of file
+    // 其内部各个 FanChannel 的 drop 方法会自动被调用。
+}
+
+fn run_info(args: InfoArgs) {
+    let report = InfoReport::collect(Path::new(&args.root.hwmon_root));
+    if report.is_empty() {
+        if let Some(e) = &report.nvml_error {
+            error!(event = "nvml_unavailable"; "无法初始化 NVML: {}", e);
+        }
+        exit(1);
+    }
+    match args.format {
+        InfoFormat::Text => report.print_text(),
+        InfoFormat::Json => println!("{}", report.to_json()),
+    }
+}
+
+fn main() {
+    let args = Args::parse();
+    logging::init(args.log.log_level, args.log.log_format);
+    match args.into_command() {
+        Command::Run(args) => run_daemon(*args),
+        Command::Info(args) => run_info(args),
+        Command::ListPwm(args) => run_list_pwm(args),
+        Command::Set(args) => run_set(args),
+        Command::Calibrate(args) => run_calibrate(args),
+        Command::Status(args) => run_status(args),
+    }
+}
@@ -0,0 +1,184 @@
+use nvml_wrapper::{
+    Device,
+    enum_wrappers::device::TemperatureSensor,
+    enums::device::SampleValue,
+    structs::device::FieldId,
+    sys_exports::field_id::NVML_FI_DEV_MEMORY_TEMP,
+};
+use std::{fmt, str::FromStr};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Metric {
+    Core,
+    Memory,
+    Power,
+    Utilization,
+}
+
+impl Metric {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Core => "core",
+            Self::Memory => "memory",
+            Self::Power => "power",
+            Self::Utilization => "utilization",
+        }
+    }
+
+    // 温度单位为 °C，功耗为 W，利用率为 %
+    pub fn read(self, device: &Device) -> Option<u32> {
+        match self {
+            Self::Core => device.temperature(TemperatureSensor::Gpu).ok(),
+            Self::Memory => {
+                let sample = device
+                    .field_values_for(&[FieldId(NVML_FI_DEV_MEMORY_TEMP)])
+                    .ok()?
+                    .pop()?
+                    .ok()?;
+                match sample.value.ok()? {
+                    SampleValue::U32(v) => Some(v),
+                    SampleValue::U64(v) => u32::try_from(v).ok(),
+                    SampleValue::I64(v) => u32::try_from(v).ok(),
+                    SampleValue::F64(v) => Some(v.max(0.0).round() as u32),
+                }
+                .filter(|&t| t > 0)
+            }
+            Self::Power => device.power_usage().ok().map(|mw| (mw + 500) / 1000),
+            Self::Utilization => device.utilization_rates().ok().map(|u| u.gpu),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MetricExpr {
+    Value { metric: Metric, offset: i32 },
+    Max(Vec<MetricExpr>),
+}
+
+impl Default for MetricExpr {
+    fn default() -> Self {
+        Self::Value {
+            metric: Metric::Core,
+            offset: 0,
+        }
+    }
+}
+
+fn split_args(s: &str) -> Vec<&str> {
+    let mut args = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                args.push(&s[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    args.push(&s[start..]);
+    args
+}
+
+impl FromStr for MetricExpr {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Some(inner) = s.strip_prefix("max(").and_then(|r| r.strip_suffix(')')) {
+            let args = split_args(inner)
+                .into_iter()
+                .map(str::parse)
+                .collect::<Result<Vec<_>, _>>()?;
+            return Ok(Self::Max(args));
+        }
+
+        let (name, offset) = match s.find(['+', '-']) {
+            Some(pos) => {
+                let offset: i32 = s[pos..]
+                    .replace(' ', "")
+                    .parse()
+                    .map_err(|_| format!("无效的指标偏移: {}", s))?;
+                (s[..pos].trim(), offset)
+            }
+            None => (s, 0),
+        };
+        let metric = match name {
+            "core" | "gpu" => Metric::Core,
+            "memory" | "mem" => Metric::Memory,
+            "power" => Metric::Power,
+            "utilization" | "util" => Metric::Utilization,
+            _ => {
+                return Err(format!(
+                    "未知的指标: {} (可选 core、memory、power、utilization 或 max(...))",
+                    name
+                ))
+            }
+        };
+        Ok(Self::Value { metric, offset })
+    }
+}
+
+impl fmt::Display for MetricExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Value { metric, offset: 0 } => write!(f, "{}", metric.name()),
+            Self::Value { metric, offset } => write!(f, "{}{:+}", metric.name(), offset),
+            Self::Max(args) => {
+                write!(f, "max(")?;
+                for (i, arg) in args.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", arg)?;
+                }
+                write!(f, ")")
+            }
+        }
+    }
+}
+
+impl MetricExpr {
+    // max(...) 忽略读取失败的项，例如不支持显存温度的显卡
+    pub fn eval(&self, read: &mut impl FnMut(Metric) -> Option<u32>) -> Option<u32> {
+        match self {
+            Self::Value { metric, offset } => {
+                read(*metric).map(|v| (v as i64 + *offset as i64).max(0) as u32)
+            }
+            Self::Max(args) => args.iter().filter_map(|arg| arg.eval(read)).max(),
+        }
+    }
+
+    pub fn eval_device(&self, device: &Device) -> Option<u32> {
+        self.eval(&mut |metric| metric.read(device))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_and_combines_metrics() {
+        let expr: MetricExpr = "max(core, memory-10)".parse().unwrap();
+        assert_eq!(expr.to_string(), "max(core, memory-10)");
+
+        let mut read = |m| match m {
+            Metric::Core => Some(65),
+            Metric::Memory => Some(82),
+            _ => None,
+        };
+        assert_eq!(expr.eval(&mut read), Some(72));
+
+        let mut no_memory = |m| (m == Metric::Core).then_some(65);
+        assert_eq!(expr.eval(&mut no_memory), Some(65));
+
+        assert_eq!("util + 5".parse::<MetricExpr>().unwrap().eval(&mut |_| Some(40)), Some(45));
+        assert_eq!("power".parse::<MetricExpr>().unwrap().to_string(), "power");
+        assert!("hotspot".parse::<MetricExpr>().is_err());
+        assert!("max(core, fan)".parse::<MetricExpr>().is_err());
+    }
+}
@@ -0,0 +1,193 @@
+use serde::Deserialize;
+
+#[derive(clap::Args, Deserialize, Debug, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct PidOverrides {
+    /// PID 模式的目标温度
+    #[arg(long = "target-temp")]
+    pub target: Option<f64>,
+    /// PID 比例系数
+    #[arg(long)]
+    pub kp: Option<f64>,
+    /// PID 积分系数
+    #[arg(long)]
+    pub ki: Option<f64>,
+    /// PID 微分系数
+    #[arg(long)]
+    pub kd: Option<f64>,
+    /// PID 输出的 PWM 下限
+    #[arg(long = "pid-min-pwm")]
+    pub min_pwm: Option<u8>,
+    /// PID 输出的 PWM 上限
+    #[arg(long = "pid-max-pwm")]
+    pub max_pwm: Option<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PidSettings {
+    pub target: f64,
+    pub kp: f64,
+    pub ki: f64,
+    pub kd: f64,
+    pub min_pwm: u8,
+    pub max_pwm: u8,
+}
+
+impl Default for PidSettings {
+    fn default() -> Self {
+        Self {
+            target: 68.0,
+            kp: 8.0,
+            ki: 0.5,
+            kd: 0.0,
+            min_pwm: 77,
+            max_pwm: 255,
+        }
+    }
+}
+
+impl PidSettings {
+    pub fn merge(mut self, o: &PidOverrides) -> Self {
+        self.target = o.target.unwrap_or(self.target);
+        self.kp = o.kp.unwrap_or(self.kp);
+        self.ki = o.ki.unwrap_or(self.ki);
+        self.kd = o.kd.unwrap_or(self.kd);
+        self.min_pwm = o.min_pwm.unwrap_or(self.min_pwm);
+        self.max_pwm = o.max_pwm.unwrap_or(self.max_pwm);
+        self
+    }
+
+    pub fn validate(self) -> Result<Self, String> {
+        if !self.target.is_finite() {
+            return Err(format!("PID 目标温度无效: {}", self.target));
+        }
+        for (name, gain) in [("kp", self.kp), ("ki", self.ki), ("kd", self.kd)] {
+            if !gain.is_finite() || gain < 0.0 {
+                return Err(format!("PID 参数 {}={} 必须为非负数", name, gain));
+            }
+        }
+        if self.min_pwm > self.max_pwm {
+            return Err(format!(
+                "PID 最小 PWM ({}) 不能大于最大 PWM ({})",
+                self.min_pwm, self.max_pwm
+            ));
+        }
+        Ok(self)
+    }
+}
+
+pub struct PidController {
+    settings: PidSettings,
+    integral: f64,
+    last_temp: Option<f64>,
+}
+
+impl PidController {
+    pub fn new(settings: PidSettings) -> Self {
+        Self {
+            settings,
+            integral: 0.0,
+            last_temp: None,
+        }
+    }
+
+    // 误差为 温度 - 目标，温度越高输出越大；dt 单位为秒
+    pub fn update(&mut self, temp: f64, dt: f64) -> u8 {
+        let s = &self.settings;
+        let (min, max) = (s.min_pwm as f64, s.max_pwm as f64);
+        let error = temp - s.target;
+
+        let derivative = match self.last_temp {
+            Some(last) if dt > 0.0 => (temp - last) / dt,
+            _ => 0.0,
+        };
+        self.last_temp = Some(temp);
+
+        let candidate = self.integral + error * dt;
+        let rest = s.kp * error + s.kd * derivative;
+
+        // 抗积分饱和：积分项最多累积到输出刚好饱和为止
+        self.integral = if s.ki > 0.0 {
+            let upper = ((max - rest) / s.ki).max(self.integral);
+            let lower = ((min - rest) / s.ki).min(self.integral);
+            candidate.clamp(lower, upper)
+        } else {
+            candidate
+        };
+
+        let output = rest + s.ki * self.integral;
+        output.clamp(min, max).round() as u8
+    }
+
+    pub fn reset(&mut self) {
+        self.last_temp = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 一阶热模型：C·dT/dt = P − (h0 + h1·pwm/255)·(T − 环境温度)
+    struct ThermalModel {
+        temp: f64,
+        power: f64,
+    }
+
+    impl ThermalModel {
+        fn step(&mut self, pwm: u8, dt: f64) {
+            let conductance = 3.0 + 5.0 * pwm as f64 / 255.0;
+            self.temp += dt * (self.power - conductance * (self.temp - 30.0)) / 50.0;
+        }
+    }
+
+    fn simulate(pid: &mut PidController, model: &mut ThermalModel, steps: usize) -> u8 {
+        let mut pwm = 0;
+        for _ in 0..steps {
+            pwm = pid.update(model.temp, 1.0);
+            model.step(pwm, 1.0);
+        }
+        pwm
+    }
+
+    #[test]
+    fn holds_target_temperature() {
+        let mut pid = PidController::new(PidSettings::default());
+        let mut model = ThermalModel { temp: 40.0, power: 200.0 };
+
+        let pwm = simulate(&mut pid, &mut model, 600);
+        assert!((model.temp - 68.0).abs() < 0.5, "temp = {}", model.temp);
+        assert!((100..=130).contains(&pwm), "pwm = {}", pwm);
+    }
+
+    #[test]
+    fn output_is_clamped() {
+        let settings = PidSettings {
+            min_pwm: 90,
+            max_pwm: 200,
+            ..PidSettings::default()
+        };
+        let mut pid = PidController::new(settings);
+        assert_eq!(pid.update(20.0, 1.0), 90);
+        assert_eq!(pid.update(120.0, 1.0), 200);
+    }
+
+    #[test]
+    fn recovers_quickly_after_saturation() {
+        let mut pid = PidController::new(PidSettings::default());
+        let mut model = ThermalModel { temp: 60.0, power: 400.0 };
+
+        // 负载过高，风扇长时间满速也达不到目标温度
+        assert_eq!(simulate(&mut pid, &mut model, 600), 255);
+        assert!(model.temp > 68.0);
+
+        // 负载下降后积分没有累积，输出应迅速离开上限
+        model.power = 100.0;
+        let mut steps = 0;
+        while pid.update(model.temp, 1.0) == 255 {
+            model.step(255, 1.0);
+            steps += 1;
+            assert!(steps < 60, "PID 输出在饱和后迟迟不回落");
+        }
+    }
+}
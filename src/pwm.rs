@@ -0,0 +1,275 @@
+use crate::error::FanError;
+use log::{error, info};
+use std::{
+    fs::{File, OpenOptions},
+    io::{Read, Seek, SeekFrom, Write},
+    path::Path,
+};
+
+struct FileBuffer {
+    path_buf: String,
+    content_buf: String,
+}
+
+impl FileBuffer {
+    fn new() -> Self {
+        Self {
+            path_buf: String::with_capacity(64),
+            content_buf: String::with_capacity(16),
+        }
+    }
+
+    fn make_enable_path(&mut self, pwm_path: &str) {
+        self.path_buf.clear();
+        self.path_buf.push_str(pwm_path);
+        self.path_buf.push_str("_enable");
+    }
+}
+
+struct CachedFiles {
+    pwm_file: Option<File>,
+    enable_file: Option<File>,
+}
+
+impl CachedFiles {
+    fn new() -> Self {
+        Self {
+            pwm_file: None,
+            enable_file: None,
+        }
+    }
+
+    fn close(&mut self) {
+        self.pwm_file = None;
+        self.enable_file = None;
+    }
+
+    fn get_or_open_pwm(&mut self, path: &str) -> Result<&mut File, FanError> {
+        let file = match self.pwm_file.take() {
+            Some(file) => file,
+            None => OpenOptions::new()
+                .write(true)
+                .open(path)
+                .map_err(|e| FanError::open(path, e))?,
+        };
+        Ok(self.pwm_file.insert(file))
+    }
+
+    fn get_or_open_enable(&mut self, path: &str) -> Result<&mut File, FanError> {
+        let file = match self.enable_file.take() {
+            Some(file) => file,
+            None => OpenOptions::new()
+                .read(true)
+                .write(true)
+                .open(path)
+                .map_err(|e| FanError::open(path, e))?,
+        };
+        Ok(self.enable_file.insert(file))
+    }
+}
+
+fn parse_u8(path: &str, content: &str) -> Result<u8, FanError> {
+    content.trim().parse().map_err(|_| FanError::Parse {
+        path: path.to_string(),
+        content: content.to_string(),
+    })
+}
+
+fn read_u8(path: &str, buf: &mut String) -> Result<u8, FanError> {
+    buf.clear();
+    File::open(path)
+        .map_err(|e| FanError::open(path, e))?
+        .read_to_string(buf)
+        .map_err(|e| FanError::read(path, e))?;
+    parse_u8(path, buf)
+}
+
+fn write_u8(file: &mut File, path: &str, val: u8) -> Result<(), FanError> {
+    file.seek(SeekFrom::Start(0))
+        .and_then(|_| file.write_all(val.to_string().as_bytes()))
+        .and_then(|_| file.flush())
+        .map_err(|e| FanError::write(path, val, e))
+}
+
+// 接管前的通道状态，退出时原样恢复
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Snapshot {
+    enable: u8,
+    pwm: Option<u8>,
+}
+
+pub struct PwmOutput {
+    pwm_path: String,
+    enable_path: String,
+    buffer: FileBuffer,
+    files: CachedFiles,
+    original: Snapshot,
+    exit_mode: Option<u8>,
+}
+
+impl PwmOutput {
+    pub fn new(pwm_path: String, exit_mode: Option<u8>) -> Result<Self, FanError> {
+        let mut buffer = FileBuffer::new();
+        buffer.make_enable_path(&pwm_path);
+        
+        if !Path::new(&buffer.path_buf).exists() {
+            return Err(FanError::MissingEnableFile {
+                path: buffer.path_buf,
+            });
+        }
+
+        let enable_path = buffer.path_buf.clone();
+        let original = Snapshot {
+            enable: read_u8(&enable_path, &mut buffer.content_buf)?,
+            pwm: read_u8(&pwm_path, &mut buffer.content_buf).ok(),
+        };
+        let mut output = Self {
+            pwm_path,
+            enable_path,
+            buffer,
+            files: CachedFiles::new(),
+            original,
+            exit_mode,
+        };
+
+        output.set_pwm_mode(1)?;
+        Ok(output)
+    }
+
+    pub fn path(&self) -> &str {
+        &self.pwm_path
+    }
+
+    // 挂起恢复或驱动重新加载后，缓存的句柄可能指向失效的 inode
+    pub fn reopen(&mut self, pwm_path: String) -> Result<(), FanError> {
+        self.files.close();
+        if pwm_path != self.pwm_path {
+            self.buffer.make_enable_path(&pwm_path);
+            self.enable_path = self.buffer.path_buf.clone();
+            self.pwm_path = pwm_path;
+        }
+        self.set_pwm_mode(1)
+    }
+
+    fn read_u8_from_enable_file(&mut self) -> Result<u8, FanError> {
+        let enable_path = self.enable_path.clone();
+        let content_buf = &mut self.buffer.content_buf;
+        let result = self.files.get_or_open_enable(&enable_path).and_then(|file| {
+            content_buf.clear();
+            file.seek(SeekFrom::Start(0))
+                .and_then(|_| file.read_to_string(content_buf))
+                .map_err(|e| FanError::read(&enable_path, e))
+        });
+        if result.is_err() {
+            self.files.enable_file = None;
+        }
+        result.and_then(|_| parse_u8(&enable_path, &self.buffer.content_buf))
+    }
+
+    fn write_u8_to_pwm_file(&mut self, val: u8) -> Result<(), FanError> {
+        let pwm_path = self.pwm_path.clone();
+        let result = self
+            .files
+            .get_or_open_pwm(&pwm_path)
+            .and_then(|file| write_u8(file, &pwm_path, val));
+        if result.is_err() {
+            self.files.pwm_file = None;
+        }
+        result
+    }
+
+    fn write_u8_to_enable_file(&mut self, val: u8) -> Result<(), FanError> {
+        let enable_path = self.enable_path.clone();
+        let result = self
+            .files
+            .get_or_open_enable(&enable_path)
+            .and_then(|file| write_u8(file, &enable_path, val));
+        if result.is_err() {
+            self.files.enable_file = None;
+        }
+        result
+    }
+
+    fn set_pwm_mode(&mut self, mode: u8) -> Result<(), FanError> {
+        if self.read_u8_from_enable_file()? != mode {
+            self.write_u8_to_enable_file(mode)?;
+        }
+        Ok(())
+    }
+
+    // 每次都重新打开，避免读到写入句柄的缓存
+    pub fn read_pwm(&mut self) -> Result<u8, FanError> {
+        read_u8(&self.pwm_path, &mut self.buffer.content_buf)
+    }
+
+    // 固件或驱动可能在挂起恢复后收回控制权，返回被改成的模式
+    pub fn reassert_manual_mode(&mut self) -> Result<Option<u8>, FanError> {
+        let current = self.read_u8_from_enable_file()?;
+        if current == 1 {
+            return Ok(None);
+        }
+        self.write_u8_to_enable_file(1)?;
+        Ok(Some(current))
+    }
+
+    // 写入失败时重新打开文件再试一次
+    pub fn set_fan_speed(&mut self, speed: u8) -> Result<(), FanError> {
+        self.write_u8_to_pwm_file(speed)
+            .or_else(|_| self.write_u8_to_pwm_file(speed))
+    }
+
+    // 先写回原 PWM 值再切换模式，手动模式下原值才能生效
+    fn cleanup(&mut self) {
+        info!(event = "cleanup_started", pwm_path = self.pwm_path; "正在执行清理...");
+        let mode = self.exit_mode.unwrap_or(self.original.enable);
+        let pwm = self.original.pwm.unwrap_or(77);
+        // 两步互不依赖，PWM 写入失败也要尝试恢复模式
+        let results = [self.set_fan_speed(pwm), self.set_pwm_mode(mode)];
+        let mut restored = true;
+        for e in results.into_iter().filter_map(Result::err) {
+            error!(event = "restore_failed", pwm_path = self.pwm_path; "恢复失败: {}", e);
+            restored = false;
+        }
+        if restored {
+            info!(event = "restored", pwm_path = self.pwm_path, pwm; "已恢复为模式 {}，PWM {}", mode, pwm);
+        }
+    }
+}
+
+// **关键修复**：为 PwmOutput 实现 Drop 特性
+impl Drop for PwmOutput {
+    fn drop(&mut self) {
+        self.cleanup();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::fake_pwm;
+    use std::fs;
+
+    #[test]
+    fn restores_original_mode_and_pwm() {
+        let (dir, pwm_path) = fake_pwm("restore", "200", "5");
+        let mut output = PwmOutput::new(pwm_path.clone(), None).unwrap();
+        assert_eq!(fs::read_to_string(dir.join("pwm1_enable")).unwrap(), "1");
+        output.set_fan_speed(100).unwrap();
+        assert_eq!(fs::read_to_string(&pwm_path).unwrap(), "100");
+
+        drop(output);
+        assert_eq!(fs::read_to_string(&pwm_path).unwrap(), "200");
+        assert_eq!(fs::read_to_string(dir.join("pwm1_enable")).unwrap(), "5");
+    }
+
+    #[test]
+    fn exit_mode_overrides_original_mode() {
+        let (dir, pwm_path) = fake_pwm("override", "150", "1");
+        let mut output = PwmOutput::new(pwm_path.clone(), Some(2)).unwrap();
+        output.set_fan_speed(255).unwrap();
+
+        drop(output);
+        assert_eq!(fs::read_to_string(&pwm_path).unwrap(), "150");
+        assert_eq!(fs::read_to_string(dir.join("pwm1_enable")).unwrap(), "2");
+    }
+}
@@ -0,0 +1,62 @@
+use std::{
+    fs,
+    time::{Duration, Instant},
+};
+
+const RESUME_GAP_FACTOR: u32 = 5;
+const MIN_RESUME_GAP: Duration = Duration::from_secs(10);
+
+// Instant 基于 CLOCK_MONOTONIC，挂起期间不计时；/proc/uptime 基于 CLOCK_BOOTTIME，包含挂起时间
+fn boot_time() -> Option<Duration> {
+    let uptime = fs::read_to_string("/proc/uptime").ok()?;
+    let secs: f64 = uptime.split_whitespace().next()?.parse().ok()?;
+    Some(Duration::from_secs_f64(secs))
+}
+
+pub struct ResumeDetector {
+    threshold: Duration,
+    start: Instant,
+    last: Option<Duration>,
+}
+
+impl ResumeDetector {
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            threshold: (interval * RESUME_GAP_FACTOR).max(MIN_RESUME_GAP),
+            start: Instant::now(),
+            last: None,
+        }
+    }
+
+    pub fn poll(&mut self) -> Option<Duration> {
+        let now = boot_time().unwrap_or_else(|| self.start.elapsed());
+        self.check(now)
+    }
+
+    // 两次循环之间的间隔远大于 --interval 时视为系统刚从挂起中恢复，返回该间隔
+    fn check(&mut self, now: Duration) -> Option<Duration> {
+        let gap = self.last.replace(now).map(|last| now.saturating_sub(last))?;
+        (gap > self.threshold).then_some(gap)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_gaps_much_longer_than_interval() {
+        let secs = Duration::from_secs;
+        let mut detector = ResumeDetector::new(secs(2));
+        assert_eq!(detector.check(secs(100)), None);
+        assert_eq!(detector.check(secs(102)), None);
+        assert_eq!(detector.check(secs(111)), None);
+        assert_eq!(detector.check(secs(400)), Some(secs(289)));
+        assert_eq!(detector.check(secs(402)), None);
+
+        let mut slow = ResumeDetector::new(secs(30));
+        assert_eq!(slow.check(secs(0)), None);
+        assert_eq!(slow.check(secs(120)), None);
+        assert_eq!(slow.check(secs(400)), Some(secs(280)));
+    }
+}
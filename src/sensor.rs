@@ -0,0 +1,83 @@
+use crate::{gpu::GpuSet, hwmon::SysfsValue};
+use nvml_wrapper::Nvml;
+use std::sync::Arc;
+
+pub trait TemperatureSource: Send {
+    fn read_temp(&mut self) -> Option<u32>;
+
+    // 日志中的 GPU 字段，非 GPU 温度来源返回 None
+    fn gpu(&self) -> Option<String> {
+        None
+    }
+}
+
+pub struct NvmlSource {
+    nvml: Arc<Nvml>,
+    gpus: GpuSet,
+}
+
+impl NvmlSource {
+    pub fn new(nvml: Arc<Nvml>, gpus: GpuSet) -> Self {
+        Self { nvml, gpus }
+    }
+}
+
+impl TemperatureSource for NvmlSource {
+    #[inline(always)]
+    fn read_temp(&mut self) -> Option<u32> {
+        self.gpus.temperature(&self.nvml)
+    }
+
+    fn gpu(&self) -> Option<String> {
+        Some(self.gpus.indices().iter().map(|i| i.to_string()).collect::<Vec<_>>().join(","))
+    }
+}
+
+// hwmon 的 tempN_input 以毫摄氏度为单位
+pub struct HwmonSource {
+    value: SysfsValue,
+}
+
+impl HwmonSource {
+    pub fn new(path: String) -> Result<Self, String> {
+        let mut source = Self {
+            value: SysfsValue::new(path),
+        };
+        if source.read_temp().is_none() {
+            return Err(format!("无法读取温度传感器: {}", source.path()));
+        }
+        Ok(source)
+    }
+
+    pub fn path(&self) -> &str {
+        self.value.path()
+    }
+}
+
+impl TemperatureSource for HwmonSource {
+    fn read_temp(&mut self) -> Option<u32> {
+        let millis: i64 = self.value.read()?;
+        Some(((millis.max(0) + 500) / 1000) as u32)
+    }
+}
+
+#[cfg(test)]
+pub struct MockSource {
+    readings: std::collections::VecDeque<Option<u32>>,
+}
+
+#[cfg(test)]
+impl MockSource {
+    pub fn new(readings: &[Option<u32>]) -> Self {
+        Self {
+            readings: readings.iter().copied().collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+impl TemperatureSource for MockSource {
+    fn read_temp(&mut self) -> Option<u32> {
+        self.readings.pop_front().flatten()
+    }
+}
@@ -0,0 +1,67 @@
+// 升降速速率单位为 PWM/秒，None 表示不限制
+pub struct SlewLimiter {
+    up: Option<f64>,
+    down: Option<f64>,
+    current: Option<f64>,
+}
+
+impl SlewLimiter {
+    pub fn new(up: Option<f64>, down: Option<f64>) -> Self {
+        Self {
+            up,
+            down,
+            current: None,
+        }
+    }
+
+    pub fn validate_rate(name: &str, rate: Option<f64>) -> Result<(), String> {
+        match rate {
+            Some(r) if !r.is_finite() || r <= 0.0 => Err(format!("{} 必须为正数: {}", name, r)),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn apply(&mut self, target: u8, dt: f64) -> u8 {
+        let target = target as f64;
+        let next = match self.current {
+            None => target,
+            Some(current) if target > current => self.up.map_or(target, |r| (current + r * dt).min(target)),
+            Some(current) => self.down.map_or(target, |r| (current - r * dt).max(target)),
+        };
+        self.current = Some(next);
+        next.round() as u8
+    }
+
+    pub fn reset_to(&mut self, speed: u8) {
+        self.current = Some(speed as f64);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limits_ramp_independently_of_tick_length() {
+        let mut slow = SlewLimiter::new(Some(50.0), Some(10.0));
+        assert_eq!(slow.apply(77, 2.0), 77);
+        assert_eq!(slow.apply(255, 2.0), 177);
+        assert_eq!(slow.apply(255, 2.0), 255);
+        assert_eq!(slow.apply(77, 2.0), 235);
+
+        let mut fast = SlewLimiter::new(Some(50.0), Some(10.0));
+        fast.reset_to(77);
+        for _ in 0..20 {
+            fast.apply(255, 0.1);
+        }
+        assert_eq!(fast.apply(255, 0.0), 177);
+        for _ in 0..40 {
+            fast.apply(77, 0.05);
+        }
+        assert_eq!(fast.apply(77, 0.0), 157);
+
+        let mut unlimited = SlewLimiter::new(None, None);
+        unlimited.reset_to(77);
+        assert_eq!(unlimited.apply(255, 0.1), 255);
+    }
+}
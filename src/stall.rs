@@ -0,0 +1,133 @@
+use crate::hwmon::SysfsValue;
+use log::error;
+use std::{
+    process::Command,
+    thread,
+    time::{Duration, Instant},
+};
+
+#[derive(Debug, Clone)]
+pub struct StallSettings {
+    pub pwm_threshold: u8,
+    pub timeout: Duration,
+    pub hook: Option<String>,
+    pub exit: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StallEvent {
+    Stalled,
+    Recovered,
+}
+
+pub struct StallDetector {
+    tach: SysfsValue,
+    settings: StallSettings,
+    since: Option<Instant>,
+    stalled: bool,
+}
+
+impl StallDetector {
+    pub fn new(fan_input: String, settings: StallSettings) -> Result<Self, String> {
+        let mut tach = SysfsValue::new(fan_input);
+        if tach.read::<u32>().is_none() {
+            return Err(format!("无法读取风扇转速: {}", tach.path()));
+        }
+        Ok(Self {
+            tach,
+            settings,
+            since: None,
+            stalled: false,
+        })
+    }
+
+    pub fn path(&self) -> &str {
+        self.tach.path()
+    }
+
+    pub fn exit_on_stall(&self) -> bool {
+        self.settings.exit
+    }
+
+    pub fn read_rpm(&mut self) -> Option<u32> {
+        self.tach.read()
+    }
+
+    // 转速读数为 0 且 PWM 高于阈值持续超过 timeout 才判定为停转
+    pub fn check(&mut self, pwm: u8, rpm: Option<u32>, now: Instant) -> Option<StallEvent> {
+        let spinning = rpm.is_none_or(|rpm| rpm > 0);
+        if spinning || pwm <= self.settings.pwm_threshold {
+            self.since = None;
+            if self.stalled && spinning {
+                self.stalled = false;
+                return Some(StallEvent::Recovered);
+            }
+            return None;
+        }
+
+        let since = *self.since.get_or_insert(now);
+        if !self.stalled && now.duration_since(since) >= self.settings.timeout {
+            self.stalled = true;
+            return Some(StallEvent::Stalled);
+        }
+        None
+    }
+
+    pub fn run_hook(&self, pwm_path: &str, pwm: u8, event: StallEvent) {
+        let Some(ref hook) = self.settings.hook else {
+            return;
+        };
+        let event = match event {
+            StallEvent::Stalled => "stalled",
+            StallEvent::Recovered => "recovered",
+        };
+        let child = Command::new("sh")
+            .arg("-c")
+            .arg(hook)
+            .env("FAN_EVENT", event)
+            .env("FAN_PWM_PATH", pwm_path)
+            .env("FAN_INPUT_PATH", self.tach.path())
+            .env("FAN_PWM", pwm.to_string())
+            .spawn();
+        match child {
+            // 在后台回收子进程，避免阻塞控制循环
+            Ok(mut child) => {
+                thread::spawn(move || child.wait());
+            }
+            Err(e) => error!(event = "stall_hook_failed", pwm_path; "无法执行停转钩子 {}: {}", hook, e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::TempDir;
+    use std::fs;
+
+    #[test]
+    fn reports_stall_after_timeout_and_recovery() {
+        let dir = TempDir::new("stall");
+        let input = dir.join("fan1_input");
+        fs::write(&input, "0\n").unwrap();
+
+        let settings = StallSettings {
+            pwm_threshold: 60,
+            timeout: Duration::from_secs(5),
+            hook: None,
+            exit: false,
+        };
+        let mut detector = StallDetector::new(input.to_string_lossy().into_owned(), settings).unwrap();
+        let t0 = Instant::now();
+        let at = |s| t0 + Duration::from_secs(s);
+
+        assert_eq!(detector.check(50, Some(0), at(0)), None);
+        assert_eq!(detector.check(50, Some(0), at(10)), None);
+        assert_eq!(detector.check(150, Some(0), at(11)), None);
+        assert_eq!(detector.check(150, Some(0), at(15)), None);
+        assert_eq!(detector.check(150, Some(0), at(16)), Some(StallEvent::Stalled));
+        assert_eq!(detector.check(150, Some(0), at(30)), None);
+        assert_eq!(detector.check(150, Some(900), at(31)), Some(StallEvent::Recovered));
+        assert_eq!(detector.check(150, Some(900), at(40)), None);
+    }
+}